@@ -1,45 +1,395 @@
-use database::create_database_connection;
+use clap::{Parser, Subcommand};
+use config::{BrokerKind, Config, ConfigArgs};
+use database::create_database_pool;
+use domain::auth::{ApiKeyRepository, Principal, Role, RoleBindingRepository};
+use domain::tenants::{TenantId, TenantScoped};
+use grpc::api_keys::ApiKeysServerImpl;
+use grpc::auth::api_key::{generate_secret, hash_secret, ApiKeyAuthenticator};
+use grpc::auth::jwt::JwtAuthenticator;
+use grpc::auth::{AuthLayer, Authenticator};
 use grpc::fields::FieldsServerImpl;
-use std::env;
+use grpc::gateway::{self, Gateway};
+use grpc::health::{service_name, HealthMonitor};
+use grpc::metrics::MetricsLayer;
+use grpc::tls::{self, TlsReloader};
+use grpc::trace::TraceLayer;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tonic::transport::server::TcpIncoming;
 use tonic::transport::Server;
 use tonic_reflection::server::Builder;
 
+use grpc::api::api_keys_server::ApiKeysServer;
 use grpc::api::fields_server::FieldsServer;
+use grpc::api::jobs_server::JobsServer;
+use grpc::api::keys_server::KeysServer;
 use grpc::api::objects_server::ObjectsServer;
+use grpc::api::role_bindings_server::RoleBindingsServer;
 use grpc::api::rules_server::RulesServer;
+use grpc::jobs::JobsServerImpl;
+use grpc::keys::KeysServerImpl;
 use grpc::objects::ObjectsServerImpl;
+use grpc::role_bindings::RoleBindingsServerImpl;
 use grpc::rules::RulesServerImpl;
 use grpc::API_DESCRIPTOR_SET;
+use repository::api_keys::ApiKeyRepositoryImpl;
 use repository::fields::FieldRepositoryImpl;
+use repository::health::HealthRepositoryImpl;
+use repository::jobs::JobRepositoryImpl;
+use repository::keys::KeyRepositoryImpl;
 use repository::objects::ObjectRepositoryImpl;
+use repository::outbox::OutboxRepositoryImpl;
+use repository::role_bindings::RoleBindingRepositoryImpl;
 use repository::rules::RuleRepositoryImpl;
+use subscriber::broker::postgres::PostgresBroker;
+use subscriber::broker::pubsub::PubSubBroker;
+use subscriber::broker::Broker;
+use subscriber::outbox::OutboxRelay;
+use subscriber::publisher::BrokerEventPublisher;
+
+#[derive(Debug, Parser)]
+#[command(about = "Serves the gRPC API")]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+    /// Print the OpenAPI document of the JSON/HTTP gateway and exit
+    #[arg(long)]
+    print_openapi: bool,
+    /// Accept every RPC without credentials, as an administrator of any tenant. Only for local
+    /// development.
+    #[arg(long)]
+    insecure_disable_auth: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Serve the API until stopped. This is the default.
+    Serve,
+    /// Create an API key with the admin role and print its secret, e.g. to call
+    /// `api.ApiKeys` and `api.RoleBindings` for the first time.
+    CreateAdminKey {
+        /// Tenant the key belongs to and administers.
+        #[arg(long, default_value = "default")]
+        tenant: String,
+        #[arg(long)]
+        name: String,
+    },
+}
+
+/// Relays events committed to the outbox to the broker in the background until `shutdown` is cancelled.
+fn spawn_outbox_relay<B: Broker>(
+    repository: OutboxRepositoryImpl,
+    broker: B,
+    config: &Config,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    let event_publisher = BrokerEventPublisher::new(
+        broker,
+        config.broker.topic_id.clone(),
+        config.broker.object_topic_id.clone(),
+    );
+    let outbox_relay = OutboxRelay::new(repository, event_publisher);
+    tokio::spawn(async move {
+        outbox_relay
+            .run(100, Duration::from_secs(1), &shutdown)
+            .await
+    })
+}
+
+/// Builds the layer authenticating RPCs with API keys and, when a JWKS file is configured, JWTs.
+fn auth_layer(
+    api_key_repository: ApiKeyRepositoryImpl,
+    role_binding_repository: RoleBindingRepositoryImpl,
+    config: &Config,
+    insecure_disable_auth: bool,
+) -> Result<AuthLayer, Box<dyn std::error::Error>> {
+    if insecure_disable_auth {
+        tracing::warn!("authentication is disabled, every caller is an administrator");
+        return Ok(AuthLayer::disabled());
+    }
+
+    let mut authenticators: Vec<Box<dyn Authenticator>> =
+        vec![Box::new(ApiKeyAuthenticator::new(api_key_repository))];
+    if let Some(jwks_path) = &config.auth.jwks_path {
+        authenticators.push(Box::new(JwtAuthenticator::from_file(
+            jwks_path,
+            config.auth.jwt_issuer.clone(),
+            config.auth.jwt_audience.clone(),
+        )?));
+    }
+
+    Ok(AuthLayer::new(authenticators, role_binding_repository))
+}
+
+/// Creates an API key bound to the admin role of `tenant_id` and prints its secret, which is
+/// not stored.
+async fn create_admin_key(
+    api_key_repository: ApiKeyRepositoryImpl,
+    role_binding_repository: RoleBindingRepositoryImpl,
+    tenant_id: &TenantId,
+    name: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_key_repository = api_key_repository.for_tenant(tenant_id);
+    let secret = generate_secret();
+    let api_key = api_key_repository
+        .create(name, hash_secret(&secret))
+        .await?;
+    let subject = Principal::api_key(&api_key).subject;
+    if let Err(e) = role_binding_repository
+        .for_tenant(tenant_id)
+        .create(subject, Role::Admin)
+        .await
+    {
+        api_key_repository.revoke(api_key.id).await?;
+        return Err(e.into());
+    }
+    tracing::info!(api_key_id = %api_key.id, "created admin API key");
+    println!("{}", secret);
+
+    Ok(())
+}
+
+/// Serves the JSON/HTTP gateway on `address`, over TLS when `tls_reloader` is set, in the
+/// background until `shutdown` is cancelled.
+async fn spawn_gateway(
+    gateway: Gateway,
+    address: SocketAddr,
+    tls_reloader: Option<&TlsReloader>,
+    shutdown: CancellationToken,
+) -> Result<JoinHandle<anyhow::Result<()>>, Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(address).await?;
+    tracing::info!(%address, "serving the JSON/HTTP gateway");
+    let gateway = match tls_reloader {
+        Some(reloader) => {
+            let incoming = tls::incoming(listener, reloader.clone(), shutdown.clone());
+            tokio::spawn(gateway.serve(incoming, shutdown))
+        }
+        None => {
+            let incoming = TcpIncoming::from_listener(listener, false, None)
+                .map_err(|e| e as Box<dyn std::error::Error>)?;
+            tokio::spawn(gateway.serve(incoming, shutdown))
+        }
+    };
+
+    Ok(gateway)
+}
+
+/// Resolves once SIGINT or SIGTERM is received.
+async fn shutdown_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("failed to listen for SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let addr = "[::1]:50051".parse()?;
+    let cli = Cli::parse();
+    if cli.print_openapi {
+        print!("{}", gateway::openapi(API_DESCRIPTOR_SET)?);
+        return Ok(());
+    }
+    let config = Config::load(&cli.config)?;
+    if cli.config.print_config {
+        print!("{}", config.masked().to_toml()?);
+        return Ok(());
+    }
+    let _telemetry = telemetry::init("server", &config.log.options())?;
+
     let reflection_server = Builder::configure()
         .register_encoded_file_descriptor_set(API_DESCRIPTOR_SET)
         .build()?;
 
     // establish database connection
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let connection = create_database_connection(database_url).await?;
+    let connection =
+        create_database_pool(config.database.url.clone(), &config.database.pool_options()).await?;
+
+    if let Some(Command::CreateAdminKey { tenant, name }) = cli.command {
+        let tenant_id = TenantId::parse(&tenant)?;
+        let result = create_admin_key(
+            ApiKeyRepositoryImpl::new(connection.clone()),
+            RoleBindingRepositoryImpl::new(connection.clone()),
+            &tenant_id,
+            name,
+        )
+        .await;
+        connection.close().await?;
+        return result;
+    }
+    let shutdown_timeout = config.shutdown_timeout();
+
+    let shutdown = CancellationToken::new();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown_signal().await;
+            shutdown.cancel();
+        }
+    });
+
+    let outbox_repository = OutboxRepositoryImpl::new(connection.clone());
+    let outbox_relay = match config.broker.kind {
+        BrokerKind::Postgres => {
+            let broker = PostgresBroker::connect(&config.database.url).await?;
+            spawn_outbox_relay(outbox_repository, broker, &config, shutdown.clone())
+        }
+        BrokerKind::PubSub => {
+            let broker = PubSubBroker::new(
+                config.broker.project_id.clone(),
+                config.broker.emulator_host.clone(),
+            )
+            .await?;
+            spawn_outbox_relay(outbox_repository, broker, &config, shutdown.clone())
+        }
+    };
 
+    let api_key_repository = ApiKeyRepositoryImpl::new(connection.clone());
+    let health_repository = HealthRepositoryImpl::new(connection.clone());
     let field_repository = FieldRepositoryImpl::new(connection.clone());
+    let job_repository = JobRepositoryImpl::new(connection.clone());
+    let key_repository = KeyRepositoryImpl::new(connection.clone());
     let object_repository = ObjectRepositoryImpl::new(connection.clone());
-    let rule_repository = RuleRepositoryImpl::new(connection);
+    let role_binding_repository = RoleBindingRepositoryImpl::new(connection.clone());
+    let rule_repository = RuleRepositoryImpl::new(connection.clone());
 
+    let auth_layer = auth_layer(
+        api_key_repository.clone(),
+        role_binding_repository.clone(),
+        &config,
+        cli.insecure_disable_auth,
+    )?;
+
+    let api_key_server = ApiKeysServerImpl::new(api_key_repository);
     let field_server = FieldsServerImpl::new(field_repository);
+    let job_server = JobsServerImpl::new(job_repository);
+    let key_server = KeysServerImpl::new(key_repository);
     let object_server = ObjectsServerImpl::new(object_repository);
+    let role_binding_server = RoleBindingsServerImpl::new(role_binding_repository);
     let rule_server = RulesServerImpl::new(rule_repository);
 
-    Server::builder()
-        .add_service(FieldsServer::new(field_server))
-        .add_service(ObjectsServer::new(object_server))
-        .add_service(RulesServer::new(rule_server))
-        .add_service(reflection_server)
-        .serve(addr)
-        .await?;
+    let api_key_server = ApiKeysServer::new(api_key_server);
+    let field_server = FieldsServer::new(field_server);
+    let job_server = JobsServer::new(job_server);
+    let key_server = KeysServer::new(key_server);
+    let object_server = ObjectsServer::new(object_server);
+    let role_binding_server = RoleBindingsServer::new(role_binding_server);
+    let rule_server = RulesServer::new(rule_server);
+
+    let (health_reporter, health_server) = tonic_health::server::health_reporter();
+    let health_monitor = HealthMonitor::new(health_repository, health_reporter);
+    let services = [
+        service_name(&api_key_server),
+        service_name(&field_server),
+        service_name(&job_server),
+        service_name(&key_server),
+        service_name(&object_server),
+        service_name(&role_binding_server),
+        service_name(&rule_server),
+    ];
+    let health = tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move { health_monitor.run(&services, None, &shutdown).await }
+    });
+
+    let metrics_server = tokio::spawn(metrics::serve(
+        config.server.metrics_address,
+        shutdown.clone(),
+    ));
+
+    // the gateway calls the same services through the same layers as gRPC clients
+    let metrics_layer = MetricsLayer::new(API_DESCRIPTOR_SET)?;
+    let api_router = || {
+        Server::builder()
+            .layer(TraceLayer)
+            .layer(metrics_layer.clone())
+            .layer(auth_layer.clone())
+            .add_service(api_key_server.clone())
+            .add_service(field_server.clone())
+            .add_service(job_server.clone())
+            .add_service(key_server.clone())
+            .add_service(object_server.clone())
+            .add_service(role_binding_server.clone())
+            .add_service(rule_server.clone())
+    };
+    let tls_reloader = config
+        .server
+        .tls
+        .options()
+        .map(TlsReloader::new)
+        .transpose()?;
+    let gateway = match config.server.rest_address {
+        Some(address) => {
+            let gateway = Gateway::new(API_DESCRIPTOR_SET, api_router().into_service())?;
+            Some(spawn_gateway(gateway, address, tls_reloader.as_ref(), shutdown.clone()).await?)
+        }
+        None => None,
+    };
+
+    // new connections are refused once the signal is received, in-flight requests are drained
+    let router = api_router()
+        .add_service(health_server)
+        .add_service(reflection_server);
+    let mut server: Pin<Box<dyn Future<Output = Result<(), tonic::transport::Error>> + Send>> =
+        match &tls_reloader {
+            Some(reloader) => {
+                let listener = TcpListener::bind(config.server.listen_address).await?;
+                let incoming = tls::incoming(listener, reloader.clone(), shutdown.clone());
+                Box::pin(
+                    router
+                        .serve_with_incoming_shutdown(incoming, shutdown.clone().cancelled_owned()),
+                )
+            }
+            None => Box::pin(router.serve_with_shutdown(
+                config.server.listen_address,
+                shutdown.clone().cancelled_owned(),
+            )),
+        };
+    let tls_reloader = tls_reloader.map(|reloader| {
+        tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { reloader.run(&shutdown).await }
+        })
+    });
+    tokio::select! {
+        result = &mut server => result?,
+        _ = shutdown.cancelled() => {
+            match tokio::time::timeout(shutdown_timeout, &mut server).await {
+                Ok(result) => result?,
+                Err(_) => tracing::warn!(
+                    ?shutdown_timeout,
+                    "in-flight requests did not finish in time, shutting down"
+                ),
+            }
+        }
+    }
+
+    // events left in the outbox are relayed after the next start
+    shutdown.cancel();
+    health.await?;
+    metrics_server.await??;
+    if let Some(gateway) = gateway {
+        match tokio::time::timeout(shutdown_timeout, gateway).await {
+            Ok(result) => result??,
+            Err(_) => tracing::warn!(
+                ?shutdown_timeout,
+                "in-flight gateway requests did not finish in time"
+            ),
+        }
+    }
+    if let Some(tls_reloader) = tls_reloader {
+        tls_reloader.await?;
+    }
+    outbox_relay.await?;
+    connection.close().await?;
 
     Ok(())
 }
@@ -1,67 +1,261 @@
-use database::create_database_connection;
-use google_cloud_default::WithAuthExt;
-use google_cloud_gax::grpc::Status;
-use google_cloud_pubsub::client::{Client, ClientConfig};
-use google_cloud_pubsub::subscription::SubscriptionConfig;
-use prost::Message;
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use config::{BrokerKind, Config, ConfigArgs};
+use database::create_database_pool;
+use domain::dead_letters::DeadLetterRepository;
+use grpc::health::HealthMonitor;
+use repository::dead_letters::DeadLetterRepositoryImpl;
+use repository::health::HealthRepositoryImpl;
+use repository::jobs::JobRepositoryImpl;
 use repository::keys::KeyRepositoryImpl;
 use repository::objects::ObjectRepositoryImpl;
+use repository::processed_messages::ProcessedMessageRepositoryImpl;
 use repository::rules::RuleRepositoryImpl;
-use std::env;
-use subscriber::keys::KeysHandler;
-use subscriber::pubsub_schema;
+use std::time::Duration;
+use subscriber::broker::postgres::PostgresBroker;
+use subscriber::broker::pubsub::PubSubBroker;
+use subscriber::broker::{Broker, Message};
+use subscriber::keys::{KeysHandler, RunOptions};
+use subscriber::retention::ProcessedMessageRetention;
+use subscriber::retry::RetryPolicy;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
+use tonic::transport::Server;
+
+#[derive(Debug, Parser)]
+#[command(about = "Generates keys from the SyncKeys messages published by the server")]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Handle messages until stopped. This is the default.
+    Run,
+    /// Inspect and replay messages that could not be handled.
+    #[command(subcommand)]
+    DeadLetters(DeadLetterCommand),
+}
+
+#[derive(Debug, Subcommand)]
+enum DeadLetterCommand {
+    /// List dead letters, oldest first.
+    List {
+        /// Include dead letters that have already been replayed.
+        #[arg(long)]
+        all: bool,
+    },
+    /// Publish a dead letter to its topic again. Each dead letter is replayed once.
+    Replay { id: i64 },
+}
 
 #[tokio::main]
-async fn main() -> Result<(), Status> {
-    let topic_id = env::var("PUBSUB_TOPIC_ID").expect("PUBSUB_TOPIC_ID must be set");
-    let subscription_id =
-        env::var("PUBSUB_SUBSCRIPTION_ID").expect("PUBSUB_SUBSCRIPTION_ID must be set");
-
-    // create pub/sub client
-    let client_config = ClientConfig::default().with_auth().await.unwrap();
-    let client = Client::new(client_config).await.unwrap();
-
-    // create subscription client
-    let subscription = client.subscription(&subscription_id);
-    if !subscription.exists(None).await? {
-        let topic = client.topic(&topic_id);
-        let config = SubscriptionConfig {
-            enable_message_ordering: true,
-            ..Default::default()
-        };
-        subscription
-            .create(topic.fully_qualified_name(), config, None)
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let config = Config::load(&cli.config)?;
+    if cli.config.print_config {
+        print!("{}", config.masked().to_toml()?);
+        return Ok(());
+    }
+    let _telemetry = telemetry::init("worker", &config.log.options())?;
+
+    // establish database connection
+    let connection =
+        create_database_pool(config.database.url.clone(), &config.database.pool_options()).await?;
+
+    let dead_letter_repository = DeadLetterRepositoryImpl::new(connection.clone());
+
+    let command = cli.command.unwrap_or(Command::Run);
+    if let Command::DeadLetters(DeadLetterCommand::List { all }) = command {
+        return list_dead_letters(&dead_letter_repository, all).await;
+    }
+
+    let object_repository = ObjectRepositoryImpl::new(connection.clone());
+    let rule_repository = RuleRepositoryImpl::new(connection.clone());
+    let key_repository = KeyRepositoryImpl::new(connection.clone());
+    let job_repository = JobRepositoryImpl::new(connection.clone());
+    let health_repository = HealthRepositoryImpl::new(connection.clone());
+    let processed_message_repository = ProcessedMessageRepositoryImpl::new(connection.clone());
+
+    let key_handler = KeysHandler::new(
+        rule_repository,
+        object_repository,
+        key_repository,
+        job_repository,
+        config.worker.sync_options(),
+    );
+
+    let result = match config.broker.kind {
+        BrokerKind::Postgres => {
+            let broker = PostgresBroker::connect(&config.database.url).await?;
+            execute(
+                command,
+                broker,
+                key_handler,
+                dead_letter_repository,
+                health_repository,
+                processed_message_repository,
+                &config,
+            )
+            .await
+        }
+        BrokerKind::PubSub => {
+            let broker = PubSubBroker::new(
+                config.broker.project_id.clone(),
+                config.broker.emulator_host.clone(),
+            )
             .await?;
+            execute(
+                command,
+                broker,
+                key_handler,
+                dead_letter_repository,
+                health_repository,
+                processed_message_repository,
+                &config,
+            )
+            .await
+        }
+    };
+    connection.close().await?;
+
+    result
+}
+
+/// Resolves once SIGINT or SIGTERM is received.
+async fn shutdown_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("failed to listen for SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+async fn execute<B: Broker>(
+    command: Command,
+    broker: B,
+    key_handler: KeysHandler<
+        RuleRepositoryImpl,
+        ObjectRepositoryImpl,
+        KeyRepositoryImpl,
+        JobRepositoryImpl,
+    >,
+    dead_letter_repository: DeadLetterRepositoryImpl,
+    health_repository: HealthRepositoryImpl,
+    processed_message_repository: ProcessedMessageRepositoryImpl,
+    config: &Config,
+) -> Result<()> {
+    match command {
+        Command::DeadLetters(DeadLetterCommand::Replay { id }) => {
+            // claimed first, so that a concurrent replay of the same dead letter fails
+            let dead_letter = dead_letter_repository.claim_replay(id).await?;
+            let message = Message {
+                data: dead_letter.data,
+                attributes: dead_letter.attributes,
+                ordering_key: dead_letter.ordering_key,
+            };
+            if let Err(e) = broker.publish(&dead_letter.topic, message).await {
+                dead_letter_repository.release_replay(id).await?;
+                return Err(e);
+            }
+
+            Ok(())
+        }
+        _ => {
+            let run_options = RunOptions {
+                topic: config.broker.topic_id.clone(),
+                subscription: config.broker.subscription_id.clone(),
+                concurrency: config.worker.concurrency(&config.database),
+                retry_policy: RetryPolicy::default(),
+                drain_timeout: config.shutdown_timeout(),
+            };
+            let shutdown = CancellationToken::new();
+            tokio::spawn({
+                let shutdown = shutdown.clone();
+                async move {
+                    shutdown_signal().await;
+                    shutdown.cancel();
+                }
+            });
+
+            // report the subscription and the database through grpc.health.v1.Health
+            let (subscribed, subscription) = watch::channel(false);
+            let (health_reporter, health_server) = tonic_health::server::health_reporter();
+            let health_monitor = HealthMonitor::new(health_repository, health_reporter);
+            let health = tokio::spawn({
+                let shutdown = shutdown.clone();
+                async move { health_monitor.run(&[], Some(subscription), &shutdown).await }
+            });
+            let health_server = tokio::spawn(
+                Server::builder()
+                    .add_service(health_server)
+                    .serve_with_shutdown(
+                        config.worker.health_address,
+                        shutdown.clone().cancelled_owned(),
+                    ),
+            );
+
+            let metrics_server = tokio::spawn(metrics::serve(
+                config.worker.metrics_address,
+                shutdown.clone(),
+            ));
+
+            let retention = ProcessedMessageRetention::new(
+                processed_message_repository,
+                config.worker.processed_message_retention(),
+            );
+            let retention = tokio::spawn({
+                let shutdown = shutdown.clone();
+                async move { retention.run(Duration::from_secs(60 * 60), &shutdown).await }
+            });
+
+            // Receive message
+            let result = key_handler
+                .run(
+                    &broker,
+                    &dead_letter_repository,
+                    &run_options,
+                    &subscribed,
+                    shutdown.clone(),
+                )
+                .await;
+            // receiving may also end because the subscription failed
+            shutdown.cancel();
+            health.await?;
+            health_server.await??;
+            metrics_server.await??;
+            retention.await?;
+
+            result
+        }
     }
+}
 
-    // Receive message
-    subscription
-        .receive(
-            |message, _cancel| async move {
-                // establish database connection
-                let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-                let connection = create_database_connection(database_url).await.unwrap();
-
-                let object_repository = ObjectRepositoryImpl::new(connection.clone());
-                let rule_repository = RuleRepositoryImpl::new(connection.clone());
-                let key_repository = KeyRepositoryImpl::new(connection);
-
-                let key_handler =
-                    KeysHandler::new(rule_repository, object_repository, key_repository);
-
-                // Handle data.
-                let request =
-                    pubsub_schema::SyncKeys::decode(message.message.data.as_slice()).unwrap();
-                key_handler.main(request).await.unwrap();
-
-                // Ack or Nack message.
-                let _ = message.ack().await;
-            },
-            CancellationToken::new(),
-            None,
-        )
-        .await?;
+async fn list_dead_letters(
+    dead_letter_repository: &DeadLetterRepositoryImpl,
+    include_replayed: bool,
+) -> Result<()> {
+    let dead_letters = dead_letter_repository.find_all(include_replayed).await?;
+    for dead_letter in dead_letters {
+        println!(
+            "{}\t{}\t{}\ttenant: {}\tattempts: {}\treplayed: {}\t{}",
+            dead_letter.id,
+            dead_letter.created_at.to_rfc3339(),
+            dead_letter.topic,
+            dead_letter.tenant_id,
+            dead_letter.attempts,
+            dead_letter
+                .replayed_at
+                .map(|v| v.to_rfc3339())
+                .unwrap_or("-".to_string()),
+            dead_letter.error,
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,322 @@
+use crate::error::DomainError;
+use crate::tenants::{TenantId, TenantScoped};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use std::str::FromStr;
+
+/// How a caller proved who they are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthMethod {
+    /// Authentication is disabled, every caller is trusted.
+    Anonymous,
+    ApiKey,
+    Jwt,
+}
+
+/// The authenticated caller of an RPC.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Principal {
+    /// `api-key:<id>` for API keys, the `sub` claim for JWTs.
+    pub subject: String,
+    pub method: AuthMethod,
+    /// Names of the roles taken from the `roles` claim of JWTs and from the role bindings of
+    /// the subject. Names that are not a [`Role`] grant nothing.
+    pub roles: Vec<String>,
+    /// Tenant the credentials belong to. Callers without one are confined to the default tenant,
+    /// unless they are anonymous.
+    pub tenant_id: Option<TenantId>,
+}
+
+/// A static credential. Only the SHA-256 hash of the secret is stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiKey {
+    pub id: String,
+    pub tenant_id: TenantId,
+    pub name: String,
+    pub created_at: DateTime<FixedOffset>,
+    pub revoked_at: Option<DateTime<FixedOffset>>,
+}
+
+/// What a caller may do, named `<resource>:<action>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    FieldsRead,
+    FieldsWrite,
+    RulesRead,
+    RulesWrite,
+    ObjectsRead,
+    ObjectsWrite,
+    KeysRead,
+    JobsRead,
+    JobsWrite,
+    /// Managing API keys and role bindings.
+    AuthAdmin,
+}
+
+/// A named set of permissions, bound to subjects by [`RoleBinding`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Admin,
+    /// Curates fields and rules.
+    DataSteward,
+    /// Writes objects, e.g. from an ingestion pipeline.
+    Ingestion,
+    Viewer,
+}
+
+/// Grants `role` to `subject` within a tenant. The subject is the `sub` claim of a JWT or the
+/// subject of an API key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoleBinding {
+    pub id: String,
+    pub tenant_id: TenantId,
+    pub subject: String,
+    pub role: Role,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 10] = [
+        Permission::FieldsRead,
+        Permission::FieldsWrite,
+        Permission::RulesRead,
+        Permission::RulesWrite,
+        Permission::ObjectsRead,
+        Permission::ObjectsWrite,
+        Permission::KeysRead,
+        Permission::JobsRead,
+        Permission::JobsWrite,
+        Permission::AuthAdmin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::FieldsRead => "fields:read",
+            Permission::FieldsWrite => "fields:write",
+            Permission::RulesRead => "rules:read",
+            Permission::RulesWrite => "rules:write",
+            Permission::ObjectsRead => "objects:read",
+            Permission::ObjectsWrite => "objects:write",
+            Permission::KeysRead => "keys:read",
+            Permission::JobsRead => "jobs:read",
+            Permission::JobsWrite => "jobs:write",
+            Permission::AuthAdmin => "auth:admin",
+        }
+    }
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [
+        Role::Admin,
+        Role::DataSteward,
+        Role::Ingestion,
+        Role::Viewer,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::DataSteward => "data-steward",
+            Role::Ingestion => "ingestion",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            Role::Admin => &Permission::ALL,
+            Role::DataSteward => &[
+                Permission::FieldsRead,
+                Permission::FieldsWrite,
+                Permission::RulesRead,
+                Permission::RulesWrite,
+                Permission::ObjectsRead,
+                Permission::KeysRead,
+                Permission::JobsRead,
+                Permission::JobsWrite,
+            ],
+            Role::Ingestion => &[Permission::ObjectsRead, Permission::ObjectsWrite],
+            Role::Viewer => &[
+                Permission::FieldsRead,
+                Permission::RulesRead,
+                Permission::ObjectsRead,
+                Permission::KeysRead,
+                Permission::JobsRead,
+            ],
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Role::ALL
+            .into_iter()
+            .find(|role| role.as_str() == s)
+            .ok_or_else(|| DomainError::InvalidArgument(format!("unknown role: {}", s)))
+    }
+}
+
+impl Principal {
+    pub fn anonymous() -> Self {
+        Self {
+            subject: "anonymous".to_string(),
+            method: AuthMethod::Anonymous,
+            roles: vec![],
+            tenant_id: None,
+        }
+    }
+
+    pub fn api_key(key: &ApiKey) -> Self {
+        Self {
+            subject: format!("api-key:{}", key.id),
+            method: AuthMethod::ApiKey,
+            roles: vec![],
+            tenant_id: Some(key.tenant_id.clone()),
+        }
+    }
+
+    /// Anonymous callers may do anything, as authentication is disabled.
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.method == AuthMethod::Anonymous
+            || self
+                .roles
+                .iter()
+                .filter_map(|role| role.parse::<Role>().ok())
+                .any(|role| role.permissions().contains(&permission))
+    }
+}
+
+#[async_trait]
+pub trait ApiKeyRepository: TenantScoped + Clone + Send + Sync + 'static {
+    /// Returns the key with this hash unless it has been revoked, whatever its tenant.
+    async fn find_active_by_hash(&self, key_hash: String) -> Result<Option<ApiKey>, DomainError>;
+    async fn find_all(&self) -> Result<Vec<ApiKey>, DomainError>;
+    async fn create(&self, name: String, key_hash: String) -> Result<ApiKey, DomainError>;
+    async fn revoke(&self, id: String) -> Result<ApiKey, DomainError>;
+}
+
+#[async_trait]
+pub trait RoleBindingRepository: TenantScoped + Clone + Send + Sync + 'static {
+    /// Lists the bindings of `subject`, or of every subject, oldest first.
+    async fn find_all(&self, subject: Option<String>) -> Result<Vec<RoleBinding>, DomainError>;
+    async fn create(&self, subject: String, role: Role) -> Result<RoleBinding, DomainError>;
+    async fn delete(&self, id: String) -> Result<(), DomainError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(roles: &[&str]) -> Principal {
+        Principal {
+            subject: "user".to_string(),
+            method: AuthMethod::Jwt,
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            tenant_id: None,
+        }
+    }
+
+    #[test]
+    fn roles_grant_their_permissions_only() {
+        let cases = [
+            (
+                Role::Admin,
+                vec![
+                    "fields:read",
+                    "fields:write",
+                    "rules:read",
+                    "rules:write",
+                    "objects:read",
+                    "objects:write",
+                    "keys:read",
+                    "jobs:read",
+                    "jobs:write",
+                    "auth:admin",
+                ],
+            ),
+            (
+                Role::DataSteward,
+                vec![
+                    "fields:read",
+                    "fields:write",
+                    "rules:read",
+                    "rules:write",
+                    "objects:read",
+                    "keys:read",
+                    "jobs:read",
+                    "jobs:write",
+                ],
+            ),
+            (Role::Ingestion, vec!["objects:read", "objects:write"]),
+            (
+                Role::Viewer,
+                vec![
+                    "fields:read",
+                    "rules:read",
+                    "objects:read",
+                    "keys:read",
+                    "jobs:read",
+                ],
+            ),
+        ];
+        for (role, granted) in cases {
+            let principal = principal(&[role.as_str()]);
+            for permission in Permission::ALL {
+                assert_eq!(
+                    principal.has_permission(permission),
+                    granted.contains(&permission.as_str()),
+                    "{} {}",
+                    role.as_str(),
+                    permission.as_str()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn roles_add_up() {
+        let principal = principal(&["ingestion", "viewer"]);
+
+        assert!(principal.has_permission(Permission::ObjectsWrite));
+        assert!(principal.has_permission(Permission::RulesRead));
+        assert!(!principal.has_permission(Permission::RulesWrite));
+    }
+
+    #[test]
+    fn unknown_roles_grant_nothing() {
+        for roles in [&[][..], &["unknown"], &["Admin"]] {
+            let principal = principal(roles);
+
+            assert!(
+                Permission::ALL
+                    .into_iter()
+                    .all(|permission| !principal.has_permission(permission)),
+                "{:?}",
+                roles
+            );
+        }
+    }
+
+    #[test]
+    fn anonymous_principals_may_do_anything() {
+        let principal = Principal::anonymous();
+
+        assert!(Permission::ALL
+            .into_iter()
+            .all(|permission| principal.has_permission(permission)));
+    }
+
+    #[test]
+    fn roles_parse_from_their_names() {
+        for role in Role::ALL {
+            assert_eq!(role.as_str().parse::<Role>().unwrap(), role);
+        }
+        assert!(matches!(
+            "owner".parse::<Role>(),
+            Err(DomainError::InvalidArgument(_))
+        ));
+    }
+}
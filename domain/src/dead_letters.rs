@@ -0,0 +1,48 @@
+use crate::error::DomainError;
+use crate::tenants::TenantId;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use std::collections::HashMap;
+
+/// A message the worker gave up on, kept with the error so that it can be inspected and replayed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeadLetter {
+    pub id: i64,
+    /// Tenant of the message. Operators list and replay the dead letters of every tenant.
+    pub tenant_id: TenantId,
+    pub topic: String,
+    pub subscription: String,
+    pub ordering_key: String,
+    pub data: Vec<u8>,
+    pub attributes: HashMap<String, String>,
+    pub error: String,
+    pub attempts: u32,
+    pub created_at: DateTime<FixedOffset>,
+    pub replayed_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewDeadLetter {
+    pub tenant_id: TenantId,
+    pub topic: String,
+    pub subscription: String,
+    pub ordering_key: String,
+    pub data: Vec<u8>,
+    pub attributes: HashMap<String, String>,
+    pub error: String,
+    pub attempts: u32,
+}
+
+#[async_trait]
+pub trait DeadLetterRepository: Clone + Send + Sync + 'static {
+    /// Lists dead letters, oldest first. Replayed ones are skipped unless `include_replayed` is set.
+    async fn find_all(&self, include_replayed: bool) -> Result<Vec<DeadLetter>, DomainError>;
+    async fn get(&self, id: i64) -> Result<Option<DeadLetter>, DomainError>;
+    async fn create(&self, dead_letter: NewDeadLetter) -> Result<DeadLetter, DomainError>;
+    /// Marks a dead letter as replayed before its message is published, so that concurrent
+    /// replays publish it once. Fails with `FailedPrecondition` when it has been replayed.
+    async fn claim_replay(&self, id: i64) -> Result<DeadLetter, DomainError>;
+    /// Undoes [`Self::claim_replay`] when the message could not be published.
+    async fn release_replay(&self, id: i64) -> Result<(), DomainError>;
+}
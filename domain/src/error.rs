@@ -4,6 +4,28 @@ use thiserror::Error;
 pub enum DomainError {
     #[error("NotFound, id id {0}")]
     NotFound(String),
+    /// The request is malformed regardless of the current state, e.g. an invalid regex.
+    #[error("InvalidArgument: [{0}]")]
+    InvalidArgument(String),
+    /// An entity with the same unique value exists.
+    #[error("AlreadyExists: [{0}]")]
+    AlreadyExists(String),
+    /// The request is valid but the current state does not allow it, e.g. it references a
+    /// missing entity.
+    #[error("FailedPrecondition: [{0}]")]
+    FailedPrecondition(String),
+    /// The caller presented no credentials or invalid ones.
+    #[error("Unauthenticated: [{0}]")]
+    Unauthenticated(String),
+    /// The caller is authenticated but not allowed to do this.
+    #[error("PermissionDenied: [{0}]")]
+    PermissionDenied(String),
+    /// A concurrent change won. Retrying the whole operation may succeed.
+    #[error("Conflict: [{0}]")]
+    Conflict(String),
+    /// A dependency is unreachable. Retrying later may succeed.
+    #[error("Unavailable: [{0}]")]
+    Unavailable(String),
     #[error("Unexpected Error: [{0}]")]
     Unexpected(String),
 }
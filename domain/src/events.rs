@@ -0,0 +1,48 @@
+use crate::error::DomainError;
+use crate::jobs::KeySyncJob;
+use crate::tenants::TenantId;
+use anyhow::Result;
+use async_trait::async_trait;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    KeySync(KeySyncJob),
+    ObjectChanged {
+        object_id: String,
+        change: ObjectChange,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectChange {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// An event stored in the outbox. `sequence` grows in the order events were written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutboxEvent {
+    pub sequence: i64,
+    pub event_id: String,
+    pub tenant_id: TenantId,
+    pub event: Event,
+    /// Request the event was recorded in, if any.
+    pub request_id: Option<String>,
+}
+
+#[async_trait]
+pub trait EventPublisher: Clone + Send + Sync + 'static {
+    async fn publish(&self, event: OutboxEvent) -> Result<(), DomainError>;
+}
+
+#[async_trait]
+pub trait OutboxRepository: Clone + Send + Sync + 'static {
+    /// Publishes at most `limit` pending events in sequence order and marks them as published.
+    /// Returns the number of published events.
+    async fn relay<P: EventPublisher>(
+        &self,
+        publisher: &P,
+        limit: u64,
+    ) -> Result<usize, DomainError>;
+}
@@ -0,0 +1,9 @@
+use crate::error::DomainError;
+use anyhow::Result;
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait HealthRepository: Clone + Send + Sync + 'static {
+    /// Fails while the database is unreachable or has migrations pending.
+    async fn check(&self) -> Result<(), DomainError>;
+}
@@ -0,0 +1,161 @@
+use crate::error::DomainError;
+use crate::tenants::{TenantId, TenantScoped};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+
+/// Regeneration of the keys of a rule, done by the worker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeySyncJob {
+    Create { rule_id: String, rule_version: i64 },
+    Update { rule_id: String, rule_version: i64 },
+    Delete { rule_id: String, rule_version: i64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobType {
+    CreateKeys,
+    UpdateKeys,
+    DeleteKeys,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub const ALL: [JobStatus; 5] = [
+        JobStatus::Pending,
+        JobStatus::Running,
+        JobStatus::Succeeded,
+        JobStatus::Failed,
+        JobStatus::Cancelled,
+    ];
+
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+
+    /// Whether a job in this status can move to `status`. A running job is started again when its
+    /// message is redelivered, and a failed one when its message is replayed from the dead letters.
+    pub fn can_become(&self, status: JobStatus) -> bool {
+        match status {
+            JobStatus::Running => matches!(
+                self,
+                JobStatus::Pending | JobStatus::Running | JobStatus::Failed
+            ),
+            JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled => {
+                matches!(self, JobStatus::Pending | JobStatus::Running)
+            }
+            JobStatus::Pending => false,
+        }
+    }
+}
+
+/// Progress of a `KeySyncJob`, whose id is the one returned by the rule RPCs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Job {
+    pub id: String,
+    pub tenant_id: TenantId,
+    pub job_type: JobType,
+    pub rule_id: String,
+    pub status: JobStatus,
+    pub processed_objects: u64,
+    /// Processed objects whose key could not be generated, e.g. without the field of the rule.
+    pub skipped_objects: u64,
+    /// Known once the worker has started the job.
+    pub total_objects: Option<u64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<FixedOffset>,
+    pub started_at: Option<DateTime<FixedOffset>>,
+    pub finished_at: Option<DateTime<FixedOffset>>,
+}
+
+#[async_trait]
+pub trait JobRepository: TenantScoped + Clone + Send + Sync + 'static {
+    async fn get(&self, id: String) -> Result<Option<Job>, DomainError>;
+    /// Reads at most `limit` jobs newest first, starting after the job `after`, which must exist.
+    async fn find_all(
+        &self,
+        rule_id: Option<String>,
+        status: Option<JobStatus>,
+        after: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<Job>, DomainError>;
+    /// Cancels the job unless it has already finished, and returns it.
+    async fn cancel(&self, id: String) -> Result<Job, DomainError>;
+    /// Marks a job as running, including a failed one whose message is replayed.
+    /// Returns false when the job has been cancelled or has succeeded.
+    async fn start(&self, id: String, total_objects: Option<u64>) -> Result<bool, DomainError>;
+    /// `skipped_objects` are counted in `processed_objects` too.
+    async fn add_progress(
+        &self,
+        id: String,
+        processed_objects: u64,
+        skipped_objects: u64,
+    ) -> Result<(), DomainError>;
+    /// Finishes a pending or running job; jobs that have already finished are left untouched.
+    async fn finish(
+        &self,
+        id: String,
+        status: JobStatus,
+        error: Option<String>,
+    ) -> Result<(), DomainError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use JobStatus::*;
+
+    #[test]
+    fn transitions() {
+        let cases = [
+            (Pending, Running, true),
+            (Pending, Succeeded, true),
+            (Pending, Failed, true),
+            (Pending, Cancelled, true),
+            (Running, Running, true),
+            (Running, Succeeded, true),
+            (Running, Failed, true),
+            (Running, Cancelled, true),
+            (Failed, Running, true),
+            (Failed, Failed, false),
+            (Failed, Cancelled, false),
+            (Succeeded, Running, false),
+            (Succeeded, Failed, false),
+            (Succeeded, Cancelled, false),
+            (Cancelled, Running, false),
+            (Cancelled, Succeeded, false),
+            (Cancelled, Cancelled, false),
+        ];
+        for (from, to, allowed) in cases {
+            assert_eq!(from.can_become(to), allowed, "{:?} -> {:?}", from, to);
+        }
+    }
+
+    #[test]
+    fn jobs_never_become_pending_again() {
+        for from in JobStatus::ALL {
+            assert!(!from.can_become(Pending), "{:?} -> Pending", from);
+        }
+    }
+
+    #[test]
+    fn finished_jobs_only_run_again_after_failing() {
+        for from in JobStatus::ALL.into_iter().filter(JobStatus::is_finished) {
+            for to in JobStatus::ALL {
+                let allowed = from == Failed && to == Running;
+                assert_eq!(from.can_become(to), allowed, "{:?} -> {:?}", from, to);
+            }
+        }
+    }
+}
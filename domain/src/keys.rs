@@ -1,25 +1,80 @@
 use crate::error::DomainError;
+use crate::objects::Object;
+use crate::tenants::{TenantId, TenantScoped};
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Key {
+    pub tenant_id: TenantId,
     pub rule_id: String,
     pub object_id: String,
     pub key: String,
 }
 
+/// Position of a key in the `(updated_at, rule_id, object_id)` export order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyCursor {
+    pub updated_at: DateTime<FixedOffset>,
+    pub rule_id: String,
+    pub object_id: String,
+}
+
 #[async_trait]
-pub trait KeyRepository: Clone + Send + Sync + 'static {
-    async fn create_by_rule(
+pub trait KeyRepository: TenantScoped + Clone + Send + Sync + 'static {
+    async fn find_by_object(&self, object_id: String) -> Result<Vec<Key>, DomainError>;
+    /// Reads at most `limit` keys of a rule ordered by object id, starting after `after`.
+    async fn find_by_rule(
+        &self,
+        rule_id: String,
+        after: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<Key>, DomainError>;
+    async fn find_objects_by_key(
+        &self,
+        rule_id: String,
+        key: String,
+    ) -> Result<Vec<Object>, DomainError>;
+    async fn find_batch(
+        &self,
+        updated_since: Option<DateTime<FixedOffset>>,
+        after: Option<KeyCursor>,
+        limit: u64,
+    ) -> Result<Vec<(Key, KeyCursor)>, DomainError>;
+    /// Whether a key sync message has already been applied.
+    async fn is_processed(&self, message_id: String) -> Result<bool, DomainError>;
+    /// Returns the last object whose key was written by the unfinished sync `message_id`.
+    async fn find_checkpoint(&self, message_id: String) -> Result<Option<String>, DomainError>;
+    /// Inserts the keys of a rule, replacing the ones that already exist for the same object.
+    async fn upsert_by_rule(
         &self,
         rule_id: String,
         keys: Vec<(String, String)>,
     ) -> Result<(), DomainError>;
-    async fn update_by_rule(
+    /// Deletes the keys of a rule for the given objects.
+    async fn delete_by_objects(
         &self,
         rule_id: String,
-        keys: Vec<(String, String)>,
+        object_ids: Vec<String>,
+    ) -> Result<(), DomainError>;
+    async fn save_checkpoint(
+        &self,
+        message_id: String,
+        rule_id: String,
+        last_object_id: String,
     ) -> Result<(), DomainError>;
-    async fn delete_by_rule(&self, rule_id: String) -> Result<(), DomainError>;
+    /// Records `message_id` as processed and drops its checkpoint.
+    async fn complete_sync(&self, message_id: String) -> Result<(), DomainError>;
+    /// Drops the checkpoint of a sync that has been given up on, a replay starts it over.
+    async fn delete_checkpoint(&self, message_id: String) -> Result<(), DomainError>;
+    /// Deletes the keys of a rule and records `message_id` as processed in the same transaction.
+    async fn delete_by_rule(&self, rule_id: String, message_id: String) -> Result<(), DomainError>;
+}
+
+/// Ids of the applied key sync messages, kept to skip their redeliveries.
+#[async_trait]
+pub trait ProcessedMessageRepository: Clone + Send + Sync + 'static {
+    /// Forgets the messages of every tenant applied before `before`, returning how many.
+    async fn delete_before(&self, before: DateTime<FixedOffset>) -> Result<u64, DomainError>;
 }
@@ -1,5 +1,11 @@
+pub mod auth;
+pub mod dead_letters;
 pub mod error;
+pub mod events;
 pub mod fields;
+pub mod health;
+pub mod jobs;
 pub mod keys;
 pub mod objects;
 pub mod rules;
+pub mod tenants;
@@ -1,23 +1,74 @@
 use crate::error::DomainError;
+use crate::tenants::{TenantId, TenantScoped};
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
 use std::collections::HashMap;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Object {
     pub id: String,
+    pub tenant_id: TenantId,
     pub attributes: HashMap<String, String>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpsertResult {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+/// Position of an object in the `(updated_at, id)` export order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectCursor {
+    pub updated_at: DateTime<FixedOffset>,
+    pub id: String,
+}
+
 #[async_trait]
-pub trait ObjectRepository: Clone + Send + Sync + 'static {
+pub trait ObjectRepository: TenantScoped + Clone + Send + Sync + 'static {
     async fn find_all(&self) -> Result<Vec<Object>, DomainError>;
+    async fn count(&self) -> Result<u64, DomainError>;
+    /// Reads at most `limit` objects ordered by id, starting after `after`.
+    /// Attributes the objects do not have are left out rather than failing the page.
+    async fn find_page(
+        &self,
+        after: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<Object>, DomainError>;
+    async fn find_batch(
+        &self,
+        updated_since: Option<DateTime<FixedOffset>>,
+        after: Option<ObjectCursor>,
+        limit: u64,
+    ) -> Result<Vec<(Object, ObjectCursor)>, DomainError>;
     async fn get(&self, id: String) -> Result<Option<Object>, DomainError>;
     async fn create(&self, attributes: HashMap<String, String>) -> Result<Object, DomainError>;
+    /// Creates the objects in a single transaction.
+    /// Records that fail validation are skipped and reported at their position in the result.
+    async fn create_many(
+        &self,
+        attributes: Vec<HashMap<String, String>>,
+    ) -> Result<Vec<Result<Object, DomainError>>, DomainError>;
+    /// Creates or updates the object identified by `source` and `external_id`.
+    async fn upsert(
+        &self,
+        source: String,
+        external_id: String,
+        attributes: HashMap<String, String>,
+    ) -> Result<(Object, UpsertResult), DomainError>;
     async fn update(
         &self,
         id: String,
         attributes: HashMap<String, String>,
     ) -> Result<Object, DomainError>;
+    /// Sets the attributes in `set` and removes the ones in `unset`, leaving the others untouched.
+    async fn patch(
+        &self,
+        id: String,
+        set: HashMap<String, String>,
+        unset: Vec<String>,
+    ) -> Result<Object, DomainError>;
     async fn delete(&self, id: String) -> Result<(), DomainError>;
 }
@@ -1,6 +1,7 @@
 use crate::error::DomainError;
 use crate::fields::Field;
 use crate::objects::Object;
+use crate::tenants::{TenantId, TenantScoped};
 use anyhow::Result;
 use async_trait::async_trait;
 use regex::Regex;
@@ -8,8 +9,11 @@ use regex::Regex;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Rule {
     pub id: String,
+    pub tenant_id: TenantId,
     pub field: Field,
     pub rule_type: GenerationRule,
+    /// Incremented on every update, so that jobs for an older version can be skipped.
+    pub version: i64,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -18,6 +22,17 @@ pub enum GenerationRule {
     Regex { pattern: String, replacer: String },
 }
 
+impl GenerationRule {
+    /// Checks that keys can be generated with the rule, e.g. that the regex compiles.
+    pub fn validate(&self) -> Result<(), DomainError> {
+        if let GenerationRule::Regex { pattern, .. } = self {
+            Regex::new(pattern).map_err(|e| DomainError::InvalidArgument(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Rule {
     pub fn generate_key(&self, object: Object) -> Result<String, DomainError> {
         match &self.rule_type {
@@ -31,7 +46,8 @@ impl Rule {
                 Ok(value.to_string())
             }
             GenerationRule::Regex { pattern, replacer } => {
-                let regex = Regex::new(pattern).unwrap();
+                let regex =
+                    Regex::new(pattern).map_err(|e| DomainError::InvalidArgument(e.to_string()))?;
                 let raw_value = object
                     .attributes
                     .get(&self.field.data_label)
@@ -48,19 +64,23 @@ impl Rule {
 }
 
 #[async_trait]
-pub trait RuleRepository: Clone + Send + Sync + 'static {
+pub trait RuleRepository: TenantScoped + Clone + Send + Sync + 'static {
     async fn find_all(&self) -> Result<Vec<Rule>, DomainError>;
     async fn get(&self, id: String) -> Result<Option<Rule>, DomainError>;
+    /// Returns the rule and the id of the job generating its keys.
     async fn create(
         &self,
         field_id: String,
         rule_type: GenerationRule,
-    ) -> Result<Rule, DomainError>;
+    ) -> Result<(Rule, String), DomainError>;
+    /// Returns the rule and the id of the job regenerating its keys.
     async fn update(
         &self,
         id: String,
         field_id: String,
         rule_type: GenerationRule,
-    ) -> Result<Rule, DomainError>;
-    async fn delete(&self, id: String) -> Result<(), DomainError>;
+    ) -> Result<(Rule, String), DomainError>;
+    /// Returns the id of the job deleting the keys of the rule, or `NotFound` without
+    /// enqueuing a job when the rule does not exist.
+    async fn delete(&self, id: String) -> Result<String, DomainError>;
 }
@@ -0,0 +1,61 @@
+use crate::error::DomainError;
+use anyhow::Result;
+use std::fmt;
+
+/// Partition of the data. Fields, rules, objects, keys and jobs of one tenant are never
+/// visible to another, and keys only match objects of the same tenant.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TenantId(String);
+
+impl TenantId {
+    /// Tenant of the data written before tenants existed, and of callers that name none.
+    pub const DEFAULT: &'static str = "default";
+    const MAX_LEN: usize = 63;
+
+    /// Accepts 1 to 63 lowercase ASCII letters, digits, `-` and `_`.
+    pub fn parse(value: &str) -> Result<Self, DomainError> {
+        let valid = !value.is_empty()
+            && value.len() <= Self::MAX_LEN
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+        if !valid {
+            return Err(DomainError::InvalidArgument(format!(
+                "invalid tenant id: {}",
+                value
+            )));
+        }
+
+        Ok(Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for TenantId {
+    fn default() -> Self {
+        Self(Self::DEFAULT.to_string())
+    }
+}
+
+/// Unchecked, for ids read back from the database.
+impl From<String> for TenantId {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for TenantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A repository whose reads and writes are confined to one tenant, the default one until it
+/// is scoped with [`TenantScoped::for_tenant`].
+pub trait TenantScoped {
+    /// Returns a copy of the repository that only sees the data of `tenant_id`.
+    fn for_tenant(&self, tenant_id: &TenantId) -> Self;
+}
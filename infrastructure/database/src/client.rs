@@ -1,4 +1,11 @@
+pub mod api_keys;
+pub mod dead_letters;
 pub mod fields;
+pub mod jobs;
+pub mod key_sync_checkpoints;
 pub mod keys;
 pub mod objects;
+pub mod outbox;
+pub mod processed_messages;
+pub mod role_bindings;
 pub mod rules;
@@ -0,0 +1,103 @@
+use crate::entities::{api_keys, api_keys::Entity as ApiKeys};
+use crate::error::db_error;
+use anyhow::Result;
+use chrono::{FixedOffset, Utc};
+use domain::error::DomainError;
+use domain::tenants::TenantId;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct PostgresApiKeyQuery<'a> {
+    conn: &'a DatabaseConnection,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresApiKeyQuery<'a> {
+    pub fn new(conn: &'a DatabaseConnection, tenant_id: &'a TenantId) -> Self {
+        Self { conn, tenant_id }
+    }
+
+    /// Looks in every tenant, as the tenant of a caller is only known once its key is found.
+    pub async fn find_active_by_hash(
+        &self,
+        key_hash: String,
+    ) -> Result<Option<api_keys::Model>, DomainError> {
+        ApiKeys::find()
+            .filter(api_keys::Column::KeyHash.eq(key_hash))
+            .filter(api_keys::Column::RevokedAt.is_null())
+            .one(self.conn)
+            .await
+            .map_err(db_error)
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<api_keys::Model>, DomainError> {
+        ApiKeys::find()
+            .filter(api_keys::Column::TenantId.eq(self.tenant_id.as_str()))
+            .order_by_asc(api_keys::Column::CreatedAt)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
+    }
+}
+
+#[derive(Debug)]
+pub struct PostgresApiKeyCommand<'a> {
+    txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresApiKeyCommand<'a> {
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
+    }
+
+    pub async fn create(
+        &self,
+        name: String,
+        key_hash: String,
+    ) -> Result<api_keys::Model, DomainError> {
+        let result = api_keys::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            name: Set(name),
+            key_hash: Set(key_hash),
+            created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            revoked_at: Set(None),
+            tenant_id: Set(self.tenant_id.to_string()),
+        }
+        .insert(self.txn)
+        .await
+        .map_err(db_error)?;
+
+        Ok(result)
+    }
+
+    /// Revoking a key twice keeps the first revocation time.
+    pub async fn revoke(&self, id: String) -> Result<api_keys::Model, DomainError> {
+        let target = ApiKeys::find_by_id(id.clone())
+            .filter(api_keys::Column::TenantId.eq(self.tenant_id.as_str()))
+            .one(self.txn)
+            .await
+            .map_err(db_error)?
+            .ok_or(DomainError::NotFound(id))?;
+        if target.revoked_at.is_some() {
+            return Ok(target);
+        }
+
+        let result = api_keys::ActiveModel {
+            revoked_at: Set(Some(
+                Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap()),
+            )),
+            ..target.into_active_model()
+        }
+        .update(self.txn)
+        .await
+        .map_err(db_error)?;
+
+        Ok(result)
+    }
+}
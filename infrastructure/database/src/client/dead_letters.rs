@@ -0,0 +1,133 @@
+use crate::entities::{dead_letters, dead_letters::Entity as DeadLetters};
+use crate::error::db_error;
+use anyhow::Result;
+use chrono::{FixedOffset, Utc};
+use domain::dead_letters::NewDeadLetter;
+use domain::error::DomainError;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::{NotSet, Set};
+use sea_orm::JsonValue as Json;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    QueryFilter, QueryOrder,
+};
+
+/// Dead letters are handled by the operators of the worker, so they span every tenant and
+/// record the tenant of their message.
+#[derive(Debug, Clone)]
+pub struct PostgresDeadLetterQuery<'a> {
+    conn: &'a DatabaseConnection,
+}
+
+impl<'a> PostgresDeadLetterQuery<'a> {
+    pub fn new(conn: &'a DatabaseConnection) -> Self {
+        Self { conn }
+    }
+
+    pub async fn find_by_id(&self, id: i64) -> Result<Option<dead_letters::Model>, DomainError> {
+        DeadLetters::find_by_id(id)
+            .one(self.conn)
+            .await
+            .map_err(db_error)
+    }
+
+    pub async fn find_all(
+        &self,
+        include_replayed: bool,
+    ) -> Result<Vec<dead_letters::Model>, DomainError> {
+        let mut query = DeadLetters::find();
+        if !include_replayed {
+            query = query.filter(dead_letters::Column::ReplayedAt.is_null());
+        }
+
+        query
+            .order_by_asc(dead_letters::Column::Id)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
+    }
+}
+
+#[derive(Debug)]
+pub struct PostgresDeadLetterCommand<'a> {
+    txn: &'a DatabaseTransaction,
+}
+
+impl<'a> PostgresDeadLetterCommand<'a> {
+    pub fn new(txn: &'a DatabaseTransaction) -> Self {
+        Self { txn }
+    }
+
+    pub async fn create(
+        &self,
+        dead_letter: NewDeadLetter,
+    ) -> Result<dead_letters::Model, DomainError> {
+        let attributes = dead_letter
+            .attributes
+            .into_iter()
+            .map(|(k, v)| (k, Json::String(v)))
+            .collect();
+
+        let result = dead_letters::ActiveModel {
+            id: NotSet,
+            topic: Set(dead_letter.topic),
+            subscription: Set(dead_letter.subscription),
+            ordering_key: Set(dead_letter.ordering_key),
+            data: Set(dead_letter.data),
+            attributes: Set(Json::Object(attributes)),
+            error: Set(dead_letter.error),
+            attempts: Set(dead_letter.attempts as i32),
+            created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            replayed_at: Set(None),
+            tenant_id: Set(dead_letter.tenant_id.to_string()),
+        }
+        .insert(self.txn)
+        .await
+        .map_err(db_error)?;
+
+        Ok(result)
+    }
+
+    /// Sets `replayed_at` unless it is already set.
+    pub async fn claim_replay(&self, id: i64) -> Result<dead_letters::Model, DomainError> {
+        let result = DeadLetters::update_many()
+            .col_expr(
+                dead_letters::Column::ReplayedAt,
+                Expr::value(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            )
+            .filter(dead_letters::Column::Id.eq(id))
+            .filter(dead_letters::Column::ReplayedAt.is_null())
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        let target = DeadLetters::find_by_id(id)
+            .one(self.txn)
+            .await
+            .map_err(db_error)?
+            .ok_or(DomainError::NotFound(id.to_string()))?;
+        if result.rows_affected == 0 {
+            return Err(DomainError::FailedPrecondition(format!(
+                "dead letter {} has already been replayed",
+                id
+            )));
+        }
+
+        Ok(target)
+    }
+
+    pub async fn release_replay(&self, id: i64) -> Result<(), DomainError> {
+        let _ = DeadLetters::update_many()
+            .col_expr(
+                dead_letters::Column::ReplayedAt,
+                Expr::value(Option::<DateTimeWithTimeZone>::None),
+            )
+            .filter(dead_letters::Column::Id.eq(id))
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+}
@@ -1,48 +1,54 @@
 use crate::entities::{fields, fields::Entity as Fields};
+use crate::error::db_error;
 use anyhow::Result;
 use chrono::{FixedOffset, Utc};
 use domain::error::DomainError;
+use domain::tenants::TenantId;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, IntoActiveModel,
-    QueryOrder,
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder,
 };
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub struct PostgresFieldQuery<'a> {
     conn: &'a DatabaseConnection,
+    tenant_id: &'a TenantId,
 }
 
 impl<'a> PostgresFieldQuery<'a> {
-    pub fn new(conn: &'a DatabaseConnection) -> Self {
-        Self { conn }
+    pub fn new(conn: &'a DatabaseConnection, tenant_id: &'a TenantId) -> Self {
+        Self { conn, tenant_id }
     }
 
     pub async fn find_by_id(&self, id: String) -> Result<Option<fields::Model>, DomainError> {
         Fields::find_by_id(id)
+            .filter(fields::Column::TenantId.eq(self.tenant_id.as_str()))
             .one(self.conn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))
+            .map_err(db_error)
     }
 
     pub async fn find_all(&self) -> Result<Vec<fields::Model>, DomainError> {
         Fields::find()
+            .filter(fields::Column::TenantId.eq(self.tenant_id.as_str()))
             .order_by_asc(fields::Column::CreatedAt)
             .all(self.conn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))
+            .map_err(db_error)
     }
 }
 
 #[derive(Debug)]
 pub struct PostgresFieldCommand<'a> {
     txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
 }
 
 impl<'a> PostgresFieldCommand<'a> {
-    pub fn new(txn: &'a DatabaseTransaction) -> Self {
-        Self { txn }
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
     }
 
     pub async fn create(
@@ -56,19 +62,21 @@ impl<'a> PostgresFieldCommand<'a> {
             label: Set(label),
             created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
             updated_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            tenant_id: Set(self.tenant_id.to_string()),
         }
         .insert(self.txn)
         .await
-        .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        .map_err(db_error)?;
 
         Ok(result)
     }
 
     pub async fn update(&self, id: String, label: String) -> Result<fields::Model, DomainError> {
         let target = Fields::find_by_id(id.to_string())
+            .filter(fields::Column::TenantId.eq(self.tenant_id.as_str()))
             .one(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?
+            .map_err(db_error)?
             .ok_or(DomainError::NotFound(id))?;
 
         let result = fields::ActiveModel {
@@ -78,16 +86,17 @@ impl<'a> PostgresFieldCommand<'a> {
         }
         .update(self.txn)
         .await
-        .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        .map_err(db_error)?;
 
         Ok(result)
     }
 
     pub async fn delete(&self, id: String) -> Result<(), DomainError> {
         let _ = Fields::delete_by_id(id)
+            .filter(fields::Column::TenantId.eq(self.tenant_id.as_str()))
             .exec(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(db_error)?;
 
         Ok(())
     }
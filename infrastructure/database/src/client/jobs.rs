@@ -0,0 +1,206 @@
+use crate::entities::sea_orm_active_enums::{JobStatus, JobType};
+use crate::entities::{jobs, jobs::Entity as Jobs};
+use crate::error::db_error;
+use anyhow::Result;
+use chrono::{FixedOffset, Utc};
+use domain::error::DomainError;
+use domain::jobs::{JobStatus as DomainJobStatus, JobType as DomainJobType};
+use domain::tenants::TenantId;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::{Expr, SimpleExpr};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveEnum, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DatabaseTransaction,
+    EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+
+#[derive(Debug, Clone)]
+pub struct PostgresJobQuery<'a> {
+    conn: &'a DatabaseConnection,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresJobQuery<'a> {
+    pub fn new(conn: &'a DatabaseConnection, tenant_id: &'a TenantId) -> Self {
+        Self { conn, tenant_id }
+    }
+
+    pub async fn find_by_id(&self, id: String) -> Result<Option<jobs::Model>, DomainError> {
+        Jobs::find_by_id(id)
+            .filter(jobs::Column::TenantId.eq(self.tenant_id.as_str()))
+            .one(self.conn)
+            .await
+            .map_err(db_error)
+    }
+
+    /// Reads at most `limit` jobs ordered by `(created_at, id)` descending, starting after `after`.
+    pub async fn find_all(
+        &self,
+        rule_id: Option<String>,
+        status: Option<DomainJobStatus>,
+        after: Option<(DateTimeWithTimeZone, String)>,
+        limit: u64,
+    ) -> Result<Vec<jobs::Model>, DomainError> {
+        let mut query = Jobs::find().filter(jobs::Column::TenantId.eq(self.tenant_id.as_str()));
+        if let Some(rule_id) = rule_id {
+            query = query.filter(jobs::Column::RuleId.eq(rule_id));
+        }
+        if let Some(status) = status {
+            query = query.filter(Expr::col(jobs::Column::Status).eq(status_value(status.into())));
+        }
+        if let Some((created_at, id)) = after {
+            query = query.filter(
+                Condition::any()
+                    .add(jobs::Column::CreatedAt.lt(created_at))
+                    .add(
+                        Condition::all()
+                            .add(jobs::Column::CreatedAt.eq(created_at))
+                            .add(jobs::Column::Id.lt(id)),
+                    ),
+            );
+        }
+
+        query
+            .order_by_desc(jobs::Column::CreatedAt)
+            .order_by_desc(jobs::Column::Id)
+            .limit(limit)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
+    }
+}
+
+#[derive(Debug)]
+pub struct PostgresJobCommand<'a> {
+    txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresJobCommand<'a> {
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
+    }
+
+    pub async fn create(
+        &self,
+        id: String,
+        job_type: DomainJobType,
+        rule_id: String,
+    ) -> Result<jobs::Model, DomainError> {
+        let result = jobs::ActiveModel {
+            id: Set(id),
+            r#type: Set(JobType::from(job_type)),
+            rule_id: Set(rule_id),
+            status: Set(JobStatus::Pending),
+            processed_objects: Set(0),
+            total_objects: Set(None),
+            error: Set(None),
+            created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            started_at: Set(None),
+            finished_at: Set(None),
+            updated_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            tenant_id: Set(self.tenant_id.to_string()),
+            skipped_objects: Set(0),
+        }
+        .insert(self.txn)
+        .await
+        .map_err(db_error)?;
+
+        Ok(result)
+    }
+
+    /// Failed jobs are started again when their message is replayed from the dead letters.
+    /// Returns false when the job does not exist, has been cancelled or has succeeded.
+    pub async fn start(&self, id: String, total_objects: Option<i64>) -> Result<bool, DomainError> {
+        let now = Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap());
+        let result = Jobs::update_many()
+            .col_expr(jobs::Column::Status, status_value(JobStatus::Running))
+            .col_expr(
+                jobs::Column::StartedAt,
+                Expr::col(jobs::Column::StartedAt).if_null(now),
+            )
+            .col_expr(jobs::Column::TotalObjects, Expr::value(total_objects))
+            .col_expr(jobs::Column::Error, Expr::value(Option::<String>::None))
+            .col_expr(
+                jobs::Column::FinishedAt,
+                Expr::value(Option::<DateTimeWithTimeZone>::None),
+            )
+            .col_expr(jobs::Column::UpdatedAt, Expr::value(now))
+            .filter(jobs::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(jobs::Column::Id.eq(id))
+            .filter(
+                Expr::col(jobs::Column::Status).is_in(statuses_becoming(DomainJobStatus::Running)),
+            )
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn add_progress(
+        &self,
+        id: String,
+        processed_objects: i64,
+        skipped_objects: i64,
+    ) -> Result<(), DomainError> {
+        let _ = Jobs::update_many()
+            .col_expr(
+                jobs::Column::ProcessedObjects,
+                Expr::col(jobs::Column::ProcessedObjects).add(processed_objects),
+            )
+            .col_expr(
+                jobs::Column::SkippedObjects,
+                Expr::col(jobs::Column::SkippedObjects).add(skipped_objects),
+            )
+            .col_expr(
+                jobs::Column::UpdatedAt,
+                Expr::value(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            )
+            .filter(jobs::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(jobs::Column::Id.eq(id))
+            .filter(Expr::col(jobs::Column::Status).eq(status_value(JobStatus::Running)))
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    /// Finishes the job unless it has already finished.
+    pub async fn finish(
+        &self,
+        id: String,
+        status: DomainJobStatus,
+        error: Option<String>,
+    ) -> Result<(), DomainError> {
+        let now = Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap());
+        let _ = Jobs::update_many()
+            .col_expr(jobs::Column::Status, status_value(status.into()))
+            .col_expr(jobs::Column::Error, Expr::value(error))
+            .col_expr(jobs::Column::FinishedAt, Expr::value(now))
+            .col_expr(jobs::Column::UpdatedAt, Expr::value(now))
+            .filter(jobs::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(jobs::Column::Id.eq(id))
+            .filter(Expr::col(jobs::Column::Status).is_in(statuses_becoming(status)))
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+}
+
+/// Statuses of the jobs that can move to `status`.
+fn statuses_becoming(status: DomainJobStatus) -> Vec<SimpleExpr> {
+    DomainJobStatus::ALL
+        .into_iter()
+        .filter(|from| from.can_become(status))
+        .map(|from| status_value(from.into()))
+        .collect()
+}
+
+/// Casts a status to the `job_status` type, which sea-orm leaves out of filters and updates.
+fn status_value(status: JobStatus) -> SimpleExpr {
+    Expr::val(status.to_value()).as_enum(JobStatus::name())
+}
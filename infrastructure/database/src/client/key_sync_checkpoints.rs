@@ -0,0 +1,84 @@
+use crate::entities::{key_sync_checkpoints, key_sync_checkpoints::Entity as KeySyncCheckpoints};
+use crate::error::db_error;
+use anyhow::Result;
+use chrono::{FixedOffset, Utc};
+use domain::error::DomainError;
+use domain::tenants::TenantId;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, QueryFilter};
+
+#[derive(Debug, Clone)]
+pub struct PostgresKeySyncCheckpointQuery<'a> {
+    conn: &'a DatabaseConnection,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresKeySyncCheckpointQuery<'a> {
+    pub fn new(conn: &'a DatabaseConnection, tenant_id: &'a TenantId) -> Self {
+        Self { conn, tenant_id }
+    }
+
+    pub async fn find_by_id(
+        &self,
+        message_id: String,
+    ) -> Result<Option<key_sync_checkpoints::Model>, DomainError> {
+        KeySyncCheckpoints::find_by_id(message_id)
+            .filter(key_sync_checkpoints::Column::TenantId.eq(self.tenant_id.as_str()))
+            .one(self.conn)
+            .await
+            .map_err(db_error)
+    }
+}
+
+#[derive(Debug)]
+pub struct PostgresKeySyncCheckpointCommand<'a> {
+    txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresKeySyncCheckpointCommand<'a> {
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
+    }
+
+    pub async fn save(
+        &self,
+        message_id: String,
+        rule_id: String,
+        last_object_id: String,
+    ) -> Result<(), DomainError> {
+        let checkpoint = key_sync_checkpoints::ActiveModel {
+            message_id: Set(message_id),
+            rule_id: Set(rule_id),
+            last_object_id: Set(last_object_id),
+            updated_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            tenant_id: Set(self.tenant_id.to_string()),
+        };
+
+        let _ = KeySyncCheckpoints::insert(checkpoint)
+            .on_conflict(
+                OnConflict::column(key_sync_checkpoints::Column::MessageId)
+                    .update_columns([
+                        key_sync_checkpoints::Column::LastObjectId,
+                        key_sync_checkpoints::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, message_id: String) -> Result<(), DomainError> {
+        let _ = KeySyncCheckpoints::delete_by_id(message_id)
+            .filter(key_sync_checkpoints::Column::TenantId.eq(self.tenant_id.as_str()))
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+}
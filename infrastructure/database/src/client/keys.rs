@@ -1,21 +1,27 @@
 use crate::entities::{keys, keys::Entity as Keys};
+use crate::error::db_error;
+use crate::MAX_BIND_PARAMETERS;
 use anyhow::Result;
 use chrono::{FixedOffset, Utc};
 use domain::error::DomainError;
+use domain::tenants::TenantId;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::OnConflict;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
-    IntoActiveModel, QueryFilter, QueryOrder,
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    IntoActiveModel, Iterable, QueryFilter, QueryOrder, QuerySelect,
 };
 
 #[derive(Debug, Clone)]
 pub struct PostgresKeyQuery<'a> {
     conn: &'a DatabaseConnection,
+    tenant_id: &'a TenantId,
 }
 
 impl<'a> PostgresKeyQuery<'a> {
-    pub fn new(conn: &'a DatabaseConnection) -> Self {
-        Self { conn }
+    pub fn new(conn: &'a DatabaseConnection, tenant_id: &'a TenantId) -> Self {
+        Self { conn, tenant_id }
     }
 
     pub async fn find_by_id(
@@ -24,30 +30,107 @@ impl<'a> PostgresKeyQuery<'a> {
         object_id: String,
     ) -> std::result::Result<Option<keys::Model>, DomainError> {
         Keys::find()
+            .filter(keys::Column::TenantId.eq(self.tenant_id.as_str()))
             .filter(keys::Column::RuleId.eq(rule_id))
             .filter(keys::Column::ObjectId.eq(object_id))
             .one(self.conn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))
+            .map_err(db_error)
     }
 
     pub async fn find_all(&self) -> Result<Vec<keys::Model>, DomainError> {
         Keys::find()
+            .filter(keys::Column::TenantId.eq(self.tenant_id.as_str()))
             .order_by_asc(keys::Column::CreatedAt)
             .all(self.conn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))
+            .map_err(db_error)
+    }
+
+    pub async fn find_by_object_id(
+        &self,
+        object_id: String,
+    ) -> Result<Vec<keys::Model>, DomainError> {
+        Keys::find()
+            .filter(keys::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(keys::Column::ObjectId.eq(object_id))
+            .order_by_asc(keys::Column::RuleId)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
+    }
+
+    /// Reads at most `limit` keys of a rule ordered by `object_id`, starting after `after`.
+    pub async fn find_by_rule_id(
+        &self,
+        rule_id: String,
+        after: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<keys::Model>, DomainError> {
+        let mut query = Keys::find()
+            .filter(keys::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(keys::Column::RuleId.eq(rule_id));
+        if let Some(object_id) = after {
+            query = query.filter(keys::Column::ObjectId.gt(object_id));
+        }
+
+        query
+            .order_by_asc(keys::Column::ObjectId)
+            .limit(limit)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
+    }
+
+    /// Reads at most `limit` keys ordered by `(updated_at, rule_id, object_id)`, starting after `after`.
+    pub async fn find_batch(
+        &self,
+        updated_since: Option<DateTimeWithTimeZone>,
+        after: Option<(DateTimeWithTimeZone, String, String)>,
+        limit: u64,
+    ) -> Result<Vec<keys::Model>, DomainError> {
+        let mut query = Keys::find().filter(keys::Column::TenantId.eq(self.tenant_id.as_str()));
+        if let Some(updated_since) = updated_since {
+            query = query.filter(keys::Column::UpdatedAt.gte(updated_since));
+        }
+        if let Some((updated_at, rule_id, object_id)) = after {
+            query = query.filter(
+                Condition::any()
+                    .add(keys::Column::UpdatedAt.gt(updated_at))
+                    .add(
+                        Condition::all()
+                            .add(keys::Column::UpdatedAt.eq(updated_at))
+                            .add(keys::Column::RuleId.gt(rule_id.clone())),
+                    )
+                    .add(
+                        Condition::all()
+                            .add(keys::Column::UpdatedAt.eq(updated_at))
+                            .add(keys::Column::RuleId.eq(rule_id))
+                            .add(keys::Column::ObjectId.gt(object_id)),
+                    ),
+            );
+        }
+
+        query
+            .order_by_asc(keys::Column::UpdatedAt)
+            .order_by_asc(keys::Column::RuleId)
+            .order_by_asc(keys::Column::ObjectId)
+            .limit(limit)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
     }
 }
 
 #[derive(Debug)]
 pub struct PostgresKeyCommand<'a> {
     txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
 }
 
 impl<'a> PostgresKeyCommand<'a> {
-    pub fn new(txn: &'a DatabaseTransaction) -> Self {
-        Self { txn }
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
     }
 
     pub async fn create(
@@ -62,19 +145,22 @@ impl<'a> PostgresKeyCommand<'a> {
             key: Set(key),
             created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
             updated_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            tenant_id: Set(self.tenant_id.to_string()),
         }
         .insert(self.txn)
         .await
-        .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        .map_err(db_error)?;
 
         Ok(result)
     }
 
+    /// Inserts the keys of a rule, replacing the ones that already exist for the same object.
     pub async fn create_many(
         &self,
         rule_id: String,
         keys: Vec<(String, String)>, // (object_id, key)
     ) -> Result<(), DomainError> {
+        let chunk_size = MAX_BIND_PARAMETERS / keys::Column::iter().count();
         let keys =
             keys.into_iter()
                 .map(|(object_id, key)| keys::ActiveModel {
@@ -87,18 +173,87 @@ impl<'a> PostgresKeyCommand<'a> {
                     updated_at: Set(
                         Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())
                     ),
+                    tenant_id: Set(self.tenant_id.to_string()),
+                })
+                .collect::<Vec<_>>();
+
+        for chunk in keys.chunks(chunk_size) {
+            let _ = Keys::insert_many(chunk.to_vec())
+                .on_conflict(
+                    OnConflict::columns([keys::Column::RuleId, keys::Column::ObjectId])
+                        .update_columns([keys::Column::Key, keys::Column::UpdatedAt])
+                        .to_owned(),
+                )
+                .exec(self.txn)
+                .await
+                .map_err(db_error)?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_many2(
+        &self,
+        object_id: String,
+        keys: Vec<(String, String)>, // (rule_id, key)
+    ) -> Result<(), DomainError> {
+        let keys =
+            keys.into_iter()
+                .map(|(rule_id, key)| keys::ActiveModel {
+                    rule_id: Set(rule_id),
+                    object_id: Set(object_id.clone()),
+                    key: Set(key),
+                    created_at: Set(
+                        Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())
+                    ),
+                    updated_at: Set(
+                        Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())
+                    ),
+                    tenant_id: Set(self.tenant_id.to_string()),
                 })
                 .collect::<Vec<_>>();
 
         let _ = Keys::insert_many(keys)
             .exec(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(db_error)?;
 
         Ok(())
     }
 
-    pub async fn create_many2(
+    pub async fn create_in_chunks(
+        &self,
+        keys: Vec<(String, String, String)>, // (rule_id, object_id, key)
+    ) -> Result<(), DomainError> {
+        let chunk_size = MAX_BIND_PARAMETERS / keys::Column::iter().count();
+        let keys =
+            keys.into_iter()
+                .map(|(rule_id, object_id, key)| keys::ActiveModel {
+                    rule_id: Set(rule_id),
+                    object_id: Set(object_id),
+                    key: Set(key),
+                    created_at: Set(
+                        Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())
+                    ),
+                    updated_at: Set(
+                        Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())
+                    ),
+                    tenant_id: Set(self.tenant_id.to_string()),
+                })
+                .collect::<Vec<_>>();
+
+        for chunk in keys.chunks(chunk_size) {
+            let _ = Keys::insert_many(chunk.to_vec())
+                .exec(self.txn)
+                .await
+                .map_err(db_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Inserts the keys of an object, replacing the ones that already exist for the same rule.
+    pub async fn upsert_by_object(
         &self,
         object_id: String,
         keys: Vec<(String, String)>, // (rule_id, key)
@@ -115,13 +270,19 @@ impl<'a> PostgresKeyCommand<'a> {
                     updated_at: Set(
                         Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())
                     ),
+                    tenant_id: Set(self.tenant_id.to_string()),
                 })
                 .collect::<Vec<_>>();
 
         let _ = Keys::insert_many(keys)
+            .on_conflict(
+                OnConflict::columns([keys::Column::RuleId, keys::Column::ObjectId])
+                    .update_columns([keys::Column::Key, keys::Column::UpdatedAt])
+                    .to_owned(),
+            )
             .exec(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(db_error)?;
 
         Ok(())
     }
@@ -133,11 +294,12 @@ impl<'a> PostgresKeyCommand<'a> {
         key: String,
     ) -> Result<keys::Model, DomainError> {
         let target = Keys::find()
+            .filter(keys::Column::TenantId.eq(self.tenant_id.as_str()))
             .filter(keys::Column::RuleId.eq(rule_id.to_string()))
             .filter(keys::Column::ObjectId.eq(object_id.to_string()))
             .one(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?
+            .map_err(db_error)?
             .ok_or(DomainError::NotFound(rule_id))?;
 
         let result = keys::ActiveModel {
@@ -147,27 +309,45 @@ impl<'a> PostgresKeyCommand<'a> {
         }
         .update(self.txn)
         .await
-        .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        .map_err(db_error)?;
 
         Ok(result)
     }
 
     pub async fn delete_by_rule_id(&self, rule_id: String) -> Result<(), DomainError> {
         let _ = Keys::delete_many()
+            .filter(keys::Column::TenantId.eq(self.tenant_id.as_str()))
             .filter(keys::Column::RuleId.eq(rule_id.to_string()))
             .exec(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+
+    pub async fn delete_by_rule_id_and_object_ids(
+        &self,
+        rule_id: String,
+        object_ids: Vec<String>,
+    ) -> Result<(), DomainError> {
+        let _ = Keys::delete_many()
+            .filter(keys::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(keys::Column::RuleId.eq(rule_id))
+            .filter(keys::Column::ObjectId.is_in(object_ids))
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
 
         Ok(())
     }
 
     pub async fn delete_by_object_id(&self, object_id: String) -> Result<(), DomainError> {
         let _ = Keys::delete_many()
+            .filter(keys::Column::TenantId.eq(self.tenant_id.as_str()))
             .filter(keys::Column::ObjectId.eq(object_id.to_string()))
             .exec(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(db_error)?;
 
         Ok(())
     }
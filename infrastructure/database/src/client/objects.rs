@@ -1,23 +1,32 @@
-use crate::entities::{objects, objects::Entity as Objects};
+use crate::entities::{keys, objects, objects::Entity as Objects};
+use crate::error::db_error;
+use crate::MAX_BIND_PARAMETERS;
 use anyhow::Result;
 use chrono::{FixedOffset, Utc};
 use domain::error::DomainError;
+use domain::objects::UpsertResult;
+use domain::tenants::TenantId;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::Expr;
 use sea_orm::ActiveValue::Set;
 use sea_orm::JsonValue as Json;
+use sea_orm::Value;
 use sea_orm::{
-    ActiveModelTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, IntoActiveModel,
-    QueryOrder,
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DatabaseTransaction, DbBackend, EntityTrait, FromQueryResult, IntoActiveModel, Iterable,
+    JoinType, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Statement,
 };
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub struct PostgresObjectQuery<'a> {
     conn: &'a DatabaseConnection,
+    tenant_id: &'a TenantId,
 }
 
 impl<'a> PostgresObjectQuery<'a> {
-    pub fn new(conn: &'a DatabaseConnection) -> Self {
-        Self { conn }
+    pub fn new(conn: &'a DatabaseConnection, tenant_id: &'a TenantId) -> Self {
+        Self { conn, tenant_id }
     }
 
     pub async fn find_by_id(
@@ -25,28 +34,162 @@ impl<'a> PostgresObjectQuery<'a> {
         id: String,
     ) -> std::result::Result<Option<objects::Model>, DomainError> {
         Objects::find_by_id(id)
+            .filter(objects::Column::TenantId.eq(self.tenant_id.as_str()))
             .one(self.conn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))
+            .map_err(db_error)
     }
 
     pub async fn find_all(&self) -> Result<Vec<objects::Model>, DomainError> {
         Objects::find()
+            .filter(objects::Column::TenantId.eq(self.tenant_id.as_str()))
             .order_by_asc(objects::Column::CreatedAt)
             .all(self.conn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))
+            .map_err(db_error)
+    }
+
+    pub async fn count(&self) -> Result<u64, DomainError> {
+        Objects::find()
+            .filter(objects::Column::TenantId.eq(self.tenant_id.as_str()))
+            .count(self.conn)
+            .await
+            .map_err(db_error)
+    }
+
+    /// Reads at most `limit` objects ordered by `id`, starting after `after`.
+    pub async fn find_page(
+        &self,
+        after: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<objects::Model>, DomainError> {
+        let mut query =
+            Objects::find().filter(objects::Column::TenantId.eq(self.tenant_id.as_str()));
+        if let Some(id) = after {
+            query = query.filter(objects::Column::Id.gt(id));
+        }
+
+        query
+            .order_by_asc(objects::Column::Id)
+            .limit(limit)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
+    }
+
+    /// Finds the objects for which `rule_id` generated `key`.
+    pub async fn find_by_key(
+        &self,
+        rule_id: String,
+        key: String,
+    ) -> Result<Vec<objects::Model>, DomainError> {
+        Objects::find()
+            .join(JoinType::InnerJoin, keys::Relation::Objects.def().rev())
+            .filter(objects::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(keys::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(keys::Column::RuleId.eq(rule_id))
+            .filter(keys::Column::Key.eq(key))
+            .order_by_asc(objects::Column::CreatedAt)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
+    }
+
+    /// Reads at most `limit` objects ordered by `(updated_at, id)`, starting after `after`.
+    pub async fn find_batch(
+        &self,
+        updated_since: Option<DateTimeWithTimeZone>,
+        after: Option<(DateTimeWithTimeZone, String)>,
+        limit: u64,
+    ) -> Result<Vec<objects::Model>, DomainError> {
+        let mut query =
+            Objects::find().filter(objects::Column::TenantId.eq(self.tenant_id.as_str()));
+        if let Some(updated_since) = updated_since {
+            query = query.filter(objects::Column::UpdatedAt.gte(updated_since));
+        }
+        if let Some((updated_at, id)) = after {
+            query = query.filter(
+                Condition::any()
+                    .add(objects::Column::UpdatedAt.gt(updated_at))
+                    .add(
+                        Condition::all()
+                            .add(objects::Column::UpdatedAt.eq(updated_at))
+                            .add(objects::Column::Id.gt(id)),
+                    ),
+            );
+        }
+
+        query
+            .order_by_asc(objects::Column::UpdatedAt)
+            .order_by_asc(objects::Column::Id)
+            .limit(limit)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
     }
 }
 
 #[derive(Debug)]
 pub struct PostgresObjectCommand<'a> {
     txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
 }
 
 impl<'a> PostgresObjectCommand<'a> {
-    pub fn new(txn: &'a DatabaseTransaction) -> Self {
-        Self { txn }
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
+    }
+
+    /// Creates the object of `source` and `external_id`, or updates its attributes, in one
+    /// statement so that concurrent upserts of a new object do not both try to insert it.
+    /// Unchanged attributes are not written.
+    pub async fn upsert(
+        &self,
+        source: String,
+        external_id: String,
+        attributes: Json,
+    ) -> Result<(objects::Model, UpsertResult), DomainError> {
+        let now = Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap());
+        // xmax is 0 on a row the statement inserted, and set on a row it updated
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO "objects" ("id", "attributes", "created_at", "updated_at", "source", "external_id", "tenant_id")
+            VALUES ($1, $2, $3, $3, $4, $5, $6)
+            ON CONFLICT ("tenant_id", "source", "external_id") DO UPDATE
+            SET "attributes" = EXCLUDED."attributes", "updated_at" = EXCLUDED."updated_at"
+            WHERE "objects"."attributes" IS DISTINCT FROM EXCLUDED."attributes"
+            RETURNING *, "xmax" = 0 AS "created""#,
+            [
+                Value::from(Uuid::new_v4().to_string()),
+                Value::from(attributes),
+                Value::from(now),
+                Value::from(source.clone()),
+                Value::from(external_id.clone()),
+                Value::from(self.tenant_id.to_string()),
+            ],
+        );
+        if let Some(row) = self.txn.query_one(statement).await.map_err(db_error)? {
+            let object = objects::Model::from_query_result(&row, "").map_err(db_error)?;
+            let created: bool = row.try_get("", "created").map_err(db_error)?;
+            let result = if created {
+                UpsertResult::Created
+            } else {
+                UpsertResult::Updated
+            };
+            return Ok((object, result));
+        }
+
+        // the conflicting row is locked by the insert until the end of the transaction
+        let object = Objects::find()
+            .filter(objects::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(objects::Column::Source.eq(source))
+            .filter(objects::Column::ExternalId.eq(external_id.clone()))
+            .one(self.txn)
+            .await
+            .map_err(db_error)?
+            .ok_or(DomainError::NotFound(external_id))?;
+
+        Ok((object, UpsertResult::Unchanged))
     }
 
     pub async fn create(&self, attributes: Json) -> Result<objects::Model, DomainError> {
@@ -55,23 +198,57 @@ impl<'a> PostgresObjectCommand<'a> {
             attributes: Set(attributes),
             created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
             updated_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            source: Set(None),
+            external_id: Set(None),
+            tenant_id: Set(self.tenant_id.to_string()),
         }
         .insert(self.txn)
         .await
-        .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        .map_err(db_error)?;
 
         Ok(result)
     }
 
+    /// Builds a new object without saving it, so that it can be validated before `create_in_chunks`.
+    pub fn build(tenant_id: &TenantId, attributes: Json) -> objects::Model {
+        objects::Model {
+            id: Uuid::new_v4().to_string(),
+            attributes,
+            created_at: Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap()),
+            updated_at: Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap()),
+            source: None,
+            external_id: None,
+            tenant_id: tenant_id.to_string(),
+        }
+    }
+
+    pub async fn create_in_chunks(&self, objects: Vec<objects::Model>) -> Result<(), DomainError> {
+        let chunk_size = MAX_BIND_PARAMETERS / objects::Column::iter().count();
+        for chunk in objects.chunks(chunk_size) {
+            let objects = chunk
+                .iter()
+                .cloned()
+                .map(IntoActiveModel::into_active_model)
+                .collect::<Vec<_>>();
+            let _ = Objects::insert_many(objects)
+                .exec(self.txn)
+                .await
+                .map_err(db_error)?;
+        }
+
+        Ok(())
+    }
+
     pub async fn update(
         &self,
         id: String,
         attributes: Json,
     ) -> Result<objects::Model, DomainError> {
         let target = Objects::find_by_id(id.to_string())
+            .filter(objects::Column::TenantId.eq(self.tenant_id.as_str()))
             .one(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?
+            .map_err(db_error)?
             .ok_or(DomainError::NotFound(id))?;
 
         let result = objects::ActiveModel {
@@ -81,16 +258,56 @@ impl<'a> PostgresObjectCommand<'a> {
         }
         .update(self.txn)
         .await
-        .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        .map_err(db_error)?;
 
         Ok(result)
     }
 
+    /// Merges `set` into the stored attributes and removes the `unset` keys with jsonb operators.
+    pub async fn merge(
+        &self,
+        id: String,
+        set: Json,
+        unset: Vec<String>,
+    ) -> Result<objects::Model, DomainError> {
+        let mut expr = r#"("attributes" || $1)"#.to_string();
+        for i in 0..unset.len() {
+            expr.push_str(&format!(" - ${}", i + 2));
+        }
+        let values = std::iter::once(Value::from(set)).chain(unset.into_iter().map(Value::from));
+
+        let result = Objects::update_many()
+            .col_expr(
+                objects::Column::Attributes,
+                Expr::cust_with_values(&expr, values),
+            )
+            .col_expr(
+                objects::Column::UpdatedAt,
+                Expr::value(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            )
+            .filter(objects::Column::TenantId.eq(self.tenant_id.as_str()))
+            .filter(objects::Column::Id.eq(id.to_string()))
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected == 0 {
+            return Err(DomainError::NotFound(id));
+        }
+
+        Objects::find_by_id(id.to_string())
+            .filter(objects::Column::TenantId.eq(self.tenant_id.as_str()))
+            .one(self.txn)
+            .await
+            .map_err(db_error)?
+            .ok_or(DomainError::NotFound(id))
+    }
+
     pub async fn delete(&self, id: String) -> Result<(), DomainError> {
         let _ = Objects::delete_by_id(id)
+            .filter(objects::Column::TenantId.eq(self.tenant_id.as_str()))
             .exec(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(db_error)?;
 
         Ok(())
     }
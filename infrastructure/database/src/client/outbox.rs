@@ -0,0 +1,144 @@
+use crate::entities::{outbox, outbox::Entity as Outbox};
+use crate::error::db_error;
+use crate::MAX_BIND_PARAMETERS;
+use anyhow::Result;
+use chrono::{FixedOffset, Utc};
+use domain::error::DomainError;
+use domain::events::{Event, ObjectChange};
+use domain::jobs::KeySyncJob;
+use domain::tenants::TenantId;
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::{NotSet, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseTransaction, EntityTrait, Iterable, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+use telemetry::request_id;
+use uuid::Uuid;
+
+pub const CREATE_KEYS: &str = "keys.create";
+pub const UPDATE_KEYS: &str = "keys.update";
+pub const DELETE_KEYS: &str = "keys.delete";
+pub const OBJECT_CREATED: &str = "objects.created";
+pub const OBJECT_UPDATED: &str = "objects.updated";
+pub const OBJECT_DELETED: &str = "objects.deleted";
+
+#[derive(Debug)]
+pub struct PostgresOutboxCommand<'a> {
+    txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresOutboxCommand<'a> {
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
+    }
+
+    pub async fn create(&self, event: Event) -> Result<outbox::Model, DomainError> {
+        let result = build(event, self.tenant_id)
+            .insert(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(result)
+    }
+
+    pub async fn create_in_chunks(&self, events: Vec<Event>) -> Result<(), DomainError> {
+        let chunk_size = MAX_BIND_PARAMETERS / outbox::Column::iter().count();
+        let events = events
+            .into_iter()
+            .map(|event| build(event, self.tenant_id))
+            .collect::<Vec<_>>();
+
+        for chunk in events.chunks(chunk_size) {
+            let _ = Outbox::insert_many(chunk.to_vec())
+                .exec(self.txn)
+                .await
+                .map_err(db_error)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Relays the events of every tenant, each event carries its own tenant.
+#[derive(Debug)]
+pub struct PostgresOutboxRelayCommand<'a> {
+    txn: &'a DatabaseTransaction,
+}
+
+impl<'a> PostgresOutboxRelayCommand<'a> {
+    pub fn new(txn: &'a DatabaseTransaction) -> Self {
+        Self { txn }
+    }
+
+    /// Locks at most `limit` unpublished events in sequence order until the transaction ends.
+    ///
+    /// Sequences are taken before commit, so an event is only read once every transaction
+    /// older than its own has ended and no event with a lower sequence can still appear.
+    pub async fn find_unpublished(&self, limit: u64) -> Result<Vec<outbox::Model>, DomainError> {
+        Outbox::find()
+            .filter(outbox::Column::PublishedAt.is_null())
+            .filter(Expr::cust(
+                r#""xid" < pg_snapshot_xmin(pg_current_snapshot())"#,
+            ))
+            .order_by_asc(outbox::Column::Sequence)
+            .limit(limit)
+            .lock_exclusive()
+            .all(self.txn)
+            .await
+            .map_err(db_error)
+    }
+
+    pub async fn mark_published(&self, sequences: Vec<i64>) -> Result<(), DomainError> {
+        if sequences.is_empty() {
+            return Ok(());
+        }
+
+        let _ = Outbox::update_many()
+            .col_expr(
+                outbox::Column::PublishedAt,
+                Expr::value(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            )
+            .filter(outbox::Column::Sequence.is_in(sequences))
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(())
+    }
+}
+
+fn build(event: Event, tenant_id: &TenantId) -> outbox::ActiveModel {
+    let (event_type, aggregate_id, aggregate_version) = match event {
+        Event::KeySync(KeySyncJob::Create {
+            rule_id,
+            rule_version,
+        }) => (CREATE_KEYS, rule_id, rule_version),
+        Event::KeySync(KeySyncJob::Update {
+            rule_id,
+            rule_version,
+        }) => (UPDATE_KEYS, rule_id, rule_version),
+        Event::KeySync(KeySyncJob::Delete {
+            rule_id,
+            rule_version,
+        }) => (DELETE_KEYS, rule_id, rule_version),
+        Event::ObjectChanged { object_id, change } => match change {
+            ObjectChange::Created => (OBJECT_CREATED, object_id, 0),
+            ObjectChange::Updated => (OBJECT_UPDATED, object_id, 0),
+            ObjectChange::Deleted => (OBJECT_DELETED, object_id, 0),
+        },
+    };
+
+    outbox::ActiveModel {
+        sequence: NotSet,
+        event_id: Set(Uuid::new_v4().to_string()),
+        event_type: Set(event_type.to_string()),
+        aggregate_id: Set(aggregate_id),
+        created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+        published_at: Set(None),
+        aggregate_version: Set(aggregate_version),
+        request_id: Set(request_id::current()),
+        tenant_id: Set(tenant_id.to_string()),
+    }
+}
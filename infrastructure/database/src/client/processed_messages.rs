@@ -0,0 +1,84 @@
+use crate::entities::{processed_messages, processed_messages::Entity as ProcessedMessages};
+use crate::error::db_error;
+use anyhow::Result;
+use chrono::{FixedOffset, Utc};
+use domain::error::DomainError;
+use domain::tenants::TenantId;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    QueryFilter,
+};
+
+#[derive(Debug, Clone)]
+pub struct PostgresProcessedMessageQuery<'a> {
+    conn: &'a DatabaseConnection,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresProcessedMessageQuery<'a> {
+    pub fn new(conn: &'a DatabaseConnection, tenant_id: &'a TenantId) -> Self {
+        Self { conn, tenant_id }
+    }
+
+    pub async fn exists(&self, message_id: String) -> Result<bool, DomainError> {
+        let result = ProcessedMessages::find_by_id(message_id)
+            .filter(processed_messages::Column::TenantId.eq(self.tenant_id.as_str()))
+            .one(self.conn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(result.is_some())
+    }
+}
+
+#[derive(Debug)]
+pub struct PostgresProcessedMessageCommand<'a> {
+    txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresProcessedMessageCommand<'a> {
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
+    }
+
+    /// Fails on the primary key when the message has already been recorded,
+    /// which rolls back a concurrent duplicate.
+    pub async fn create(&self, message_id: String) -> Result<(), DomainError> {
+        let _ = processed_messages::ActiveModel {
+            message_id: Set(message_id),
+            processed_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            tenant_id: Set(self.tenant_id.to_string()),
+        }
+        .insert(self.txn)
+        .await
+        .map_err(db_error)?;
+
+        Ok(())
+    }
+}
+
+/// Forgets the processed messages of every tenant.
+#[derive(Debug)]
+pub struct PostgresProcessedMessageRetentionCommand<'a> {
+    txn: &'a DatabaseTransaction,
+}
+
+impl<'a> PostgresProcessedMessageRetentionCommand<'a> {
+    pub fn new(txn: &'a DatabaseTransaction) -> Self {
+        Self { txn }
+    }
+
+    /// Returns the number of messages processed before `before` that were deleted.
+    pub async fn delete_before(&self, before: DateTimeWithTimeZone) -> Result<u64, DomainError> {
+        let result = ProcessedMessages::delete_many()
+            .filter(processed_messages::Column::ProcessedAt.lt(before))
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(result.rows_affected)
+    }
+}
@@ -0,0 +1,86 @@
+use crate::entities::{role_bindings, role_bindings::Entity as RoleBindings};
+use crate::error::db_error;
+use anyhow::Result;
+use chrono::{FixedOffset, Utc};
+use domain::auth::Role;
+use domain::error::DomainError;
+use domain::tenants::TenantId;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    QueryFilter, QueryOrder,
+};
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct PostgresRoleBindingQuery<'a> {
+    conn: &'a DatabaseConnection,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresRoleBindingQuery<'a> {
+    pub fn new(conn: &'a DatabaseConnection, tenant_id: &'a TenantId) -> Self {
+        Self { conn, tenant_id }
+    }
+
+    pub async fn find_all(
+        &self,
+        subject: Option<String>,
+    ) -> Result<Vec<role_bindings::Model>, DomainError> {
+        let mut query = RoleBindings::find()
+            .filter(role_bindings::Column::TenantId.eq(self.tenant_id.as_str()));
+        if let Some(subject) = subject {
+            query = query.filter(role_bindings::Column::Subject.eq(subject));
+        }
+
+        query
+            .order_by_asc(role_bindings::Column::CreatedAt)
+            .all(self.conn)
+            .await
+            .map_err(db_error)
+    }
+}
+
+#[derive(Debug)]
+pub struct PostgresRoleBindingCommand<'a> {
+    txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
+}
+
+impl<'a> PostgresRoleBindingCommand<'a> {
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
+    }
+
+    pub async fn create(
+        &self,
+        subject: String,
+        role: Role,
+    ) -> Result<role_bindings::Model, DomainError> {
+        let result = role_bindings::ActiveModel {
+            id: Set(Uuid::new_v4().to_string()),
+            subject: Set(subject),
+            role: Set(role.as_str().to_string()),
+            created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            tenant_id: Set(self.tenant_id.to_string()),
+        }
+        .insert(self.txn)
+        .await
+        .map_err(db_error)?;
+
+        Ok(result)
+    }
+
+    pub async fn delete(&self, id: String) -> Result<(), DomainError> {
+        let result = RoleBindings::delete_by_id(id.clone())
+            .filter(role_bindings::Column::TenantId.eq(self.tenant_id.as_str()))
+            .exec(self.txn)
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected == 0 {
+            return Err(DomainError::NotFound(id));
+        }
+
+        Ok(())
+    }
+}
@@ -1,25 +1,28 @@
 use crate::entities::sea_orm_active_enums::GenerationType;
 use crate::entities::{fields, fields::Entity as Fields};
 use crate::entities::{rules, rules::Entity as Rules};
+use crate::error::db_error;
 use anyhow::Result;
 use chrono::{FixedOffset, Utc};
 use domain::error::DomainError;
 use domain::rules::GenerationRule;
+use domain::tenants::TenantId;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, IntoActiveModel,
-    ModelTrait, QueryOrder,
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    IntoActiveModel, ModelTrait, QueryFilter, QueryOrder, QuerySelect,
 };
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub struct PostgresRuleQuery<'a> {
     conn: &'a DatabaseConnection,
+    tenant_id: &'a TenantId,
 }
 
 impl<'a> PostgresRuleQuery<'a> {
-    pub fn new(conn: &'a DatabaseConnection) -> Self {
-        Self { conn }
+    pub fn new(conn: &'a DatabaseConnection, tenant_id: &'a TenantId) -> Self {
+        Self { conn, tenant_id }
     }
 
     pub async fn find_by_id(
@@ -27,10 +30,11 @@ impl<'a> PostgresRuleQuery<'a> {
         id: String,
     ) -> std::result::Result<Option<(rules::Model, fields::Model)>, DomainError> {
         let result = Rules::find_by_id(id)
+            .filter(rules::Column::TenantId.eq(self.tenant_id.as_str()))
             .find_also_related(Fields)
             .one(self.conn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(db_error)?;
 
         match result {
             Some((rule, Some(field))) => Ok(Some((rule, field))),
@@ -41,11 +45,12 @@ impl<'a> PostgresRuleQuery<'a> {
 
     pub async fn find_all(&self) -> Result<Vec<(rules::Model, fields::Model)>, DomainError> {
         let results = Rules::find()
+            .filter(rules::Column::TenantId.eq(self.tenant_id.as_str()))
             .order_by_asc(rules::Column::CreatedAt)
             .find_also_related(Fields)
             .all(self.conn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(db_error)?;
 
         results
             .into_iter()
@@ -60,11 +65,12 @@ impl<'a> PostgresRuleQuery<'a> {
 #[derive(Debug)]
 pub struct PostgresRuleCommand<'a> {
     txn: &'a DatabaseTransaction,
+    tenant_id: &'a TenantId,
 }
 
 impl<'a> PostgresRuleCommand<'a> {
-    pub fn new(txn: &'a DatabaseTransaction) -> Self {
-        Self { txn }
+    pub fn new(txn: &'a DatabaseTransaction, tenant_id: &'a TenantId) -> Self {
+        Self { txn, tenant_id }
     }
 
     pub async fn create(
@@ -82,17 +88,19 @@ impl<'a> PostgresRuleCommand<'a> {
             regex_replacer: Set(rule_value.regex_replacer),
             created_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
             updated_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            version: Set(1),
+            tenant_id: Set(self.tenant_id.to_string()),
         }
         .insert(self.txn)
         .await
-        .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        .map_err(db_error)?;
 
         // select related entity
         let field = rule
             .find_related(Fields)
             .one(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?
+            .map_err(db_error)?
             .ok_or(DomainError::NotFound(rule.field_id.clone()))?;
 
         Ok((rule, field))
@@ -104,12 +112,14 @@ impl<'a> PostgresRuleCommand<'a> {
         field_id: String,
         rule_type: GenerationRule,
     ) -> Result<(rules::Model, fields::Model), DomainError> {
-        // update
+        // the row stays locked until the transaction ends, so concurrent updates get distinct versions
         let target = Rules::find_by_id(id.to_string())
+            .filter(rules::Column::TenantId.eq(self.tenant_id.as_str()))
+            .lock_exclusive()
             .one(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?
-            .ok_or(DomainError::NotFound(field_id.clone()))?;
+            .map_err(db_error)?
+            .ok_or(DomainError::NotFound(id))?;
         let rule_value: RuleValues = rule_type.into();
         let rule = rules::ActiveModel {
             field_id: Set(field_id),
@@ -117,30 +127,38 @@ impl<'a> PostgresRuleCommand<'a> {
             regex_pattern: Set(rule_value.regex_pattern),
             regex_replacer: Set(rule_value.regex_replacer),
             updated_at: Set(Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap())),
+            version: Set(target.version + 1),
             ..target.into_active_model()
         }
         .update(self.txn)
         .await
-        .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        .map_err(db_error)?;
 
         // select related entity
         let field = rule
             .find_related(Fields)
             .one(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?
+            .map_err(db_error)?
             .ok_or(DomainError::NotFound(rule.field_id.clone()))?;
 
         Ok((rule, field))
     }
 
-    pub async fn delete(&self, id: String) -> Result<(), DomainError> {
+    /// Returns the deleted rule, if it existed.
+    pub async fn delete(&self, id: String) -> Result<Option<rules::Model>, DomainError> {
+        let target = Rules::find_by_id(id.to_string())
+            .filter(rules::Column::TenantId.eq(self.tenant_id.as_str()))
+            .one(self.txn)
+            .await
+            .map_err(db_error)?;
         let _ = Rules::delete_by_id(id)
+            .filter(rules::Column::TenantId.eq(self.tenant_id.as_str()))
             .exec(self.txn)
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(db_error)?;
 
-        Ok(())
+        Ok(target)
     }
 }
 
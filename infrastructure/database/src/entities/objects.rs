@@ -11,6 +11,9 @@ pub struct Model {
     pub attributes: Json,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub source: Option<String>,
+    pub external_id: Option<String>,
+    pub tenant_id: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,6 +1,13 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.2
 
+pub use super::api_keys::Entity as ApiKeys;
+pub use super::dead_letters::Entity as DeadLetters;
 pub use super::fields::Entity as Fields;
+pub use super::jobs::Entity as Jobs;
+pub use super::key_sync_checkpoints::Entity as KeySyncCheckpoints;
 pub use super::keys::Entity as Keys;
 pub use super::objects::Entity as Objects;
+pub use super::outbox::Entity as Outbox;
+pub use super::processed_messages::Entity as ProcessedMessages;
+pub use super::role_bindings::Entity as RoleBindings;
 pub use super::rules::Entity as Rules;
@@ -10,3 +10,29 @@ pub enum GenerationType {
     #[sea_orm(string_value = "Regex")]
     Regex,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "job_status")]
+pub enum JobStatus {
+    #[sea_orm(string_value = "Cancelled")]
+    Cancelled,
+    #[sea_orm(string_value = "Failed")]
+    Failed,
+    #[sea_orm(string_value = "Pending")]
+    Pending,
+    #[sea_orm(string_value = "Running")]
+    Running,
+    #[sea_orm(string_value = "Succeeded")]
+    Succeeded,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "job_type")]
+pub enum JobType {
+    #[sea_orm(string_value = "CreateKeys")]
+    CreateKeys,
+    #[sea_orm(string_value = "DeleteKeys")]
+    DeleteKeys,
+    #[sea_orm(string_value = "UpdateKeys")]
+    UpdateKeys,
+}
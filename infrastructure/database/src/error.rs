@@ -0,0 +1,171 @@
+use domain::error::DomainError;
+use sea_orm::{DbErr, RuntimeErr, TransactionError};
+use sqlx::postgres::PgDatabaseError;
+
+/// Classifies a database error by its SQLSTATE, so that bad input and contention are told apart
+/// from failures.
+pub fn db_error(error: DbErr) -> DomainError {
+    let runtime = match &error {
+        DbErr::ConnectionAcquire => return DomainError::Unavailable(error.to_string()),
+        DbErr::RecordNotFound(e) => return DomainError::NotFound(e.clone()),
+        DbErr::Conn(_) => return DomainError::Unavailable(error.to_string()),
+        DbErr::Exec(RuntimeErr::SqlxError(e)) | DbErr::Query(RuntimeErr::SqlxError(e)) => e,
+        _ => return DomainError::Unexpected(error.to_string()),
+    };
+    let database = match runtime {
+        sqlx::Error::Database(database) => database,
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => return DomainError::Unavailable(error.to_string()),
+        _ => return DomainError::Unexpected(error.to_string()),
+    };
+    let Some(code) = database.code() else {
+        return DomainError::Unexpected(error.to_string());
+    };
+    // the detail names the offending values, e.g. `Key (id)=(1) already exists.`
+    let message = database
+        .try_downcast_ref::<PgDatabaseError>()
+        .and_then(PgDatabaseError::detail)
+        .unwrap_or_else(|| database.message())
+        .to_string();
+
+    match code.as_ref() {
+        // unique_violation, exclusion_violation
+        "23505" | "23P01" => DomainError::AlreadyExists(message),
+        // foreign_key_violation
+        "23503" => DomainError::FailedPrecondition(message),
+        // not_null_violation, check_violation, data_exception
+        "23502" | "23514" => DomainError::InvalidArgument(message),
+        c if c.starts_with("22") => DomainError::InvalidArgument(message),
+        // serialization_failure, deadlock_detected, lock_not_available
+        "40001" | "40P01" | "55P03" => DomainError::Conflict(message),
+        // connection_exception, insufficient_resources, operator_intervention
+        c if c.starts_with("08") || c.starts_with("53") || c.starts_with("57") => {
+            DomainError::Unavailable(message)
+        }
+        _ => DomainError::Unexpected(error.to_string()),
+    }
+}
+
+/// Returns the error of a transaction, either the one raised inside it or the classified
+/// error of beginning or committing it.
+pub fn transaction_error(error: TransactionError<DomainError>) -> DomainError {
+    match error {
+        TransactionError::Connection(e) => db_error(e),
+        TransactionError::Transaction(e) => e,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use std::error::Error;
+    use std::fmt;
+
+    /// A database error with a SQLSTATE and no Postgres detail.
+    #[derive(Debug)]
+    struct SqlState(&'static str);
+
+    impl fmt::Display for SqlState {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "SQLSTATE {}", self.0)
+        }
+    }
+
+    impl Error for SqlState {}
+
+    impl sqlx::error::DatabaseError for SqlState {
+        fn message(&self) -> &str {
+            "message"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+
+        fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+            self
+        }
+    }
+
+    fn variant(error: &DomainError) -> &'static str {
+        match error {
+            DomainError::NotFound(_) => "NotFound",
+            DomainError::InvalidArgument(_) => "InvalidArgument",
+            DomainError::AlreadyExists(_) => "AlreadyExists",
+            DomainError::FailedPrecondition(_) => "FailedPrecondition",
+            DomainError::Unauthenticated(_) => "Unauthenticated",
+            DomainError::PermissionDenied(_) => "PermissionDenied",
+            DomainError::Conflict(_) => "Conflict",
+            DomainError::Unavailable(_) => "Unavailable",
+            DomainError::Unexpected(_) => "Unexpected",
+        }
+    }
+
+    #[test]
+    fn database_errors_are_classified_by_sqlstate() {
+        let cases = [
+            ("23505", "AlreadyExists"),
+            ("23P01", "AlreadyExists"),
+            ("23503", "FailedPrecondition"),
+            ("23502", "InvalidArgument"),
+            ("23514", "InvalidArgument"),
+            ("22P02", "InvalidArgument"),
+            ("40001", "Conflict"),
+            ("40P01", "Conflict"),
+            ("55P03", "Conflict"),
+            ("08006", "Unavailable"),
+            ("53300", "Unavailable"),
+            ("57P01", "Unavailable"),
+            ("42P01", "Unexpected"),
+        ];
+        for (code, expected) in cases {
+            let database = sqlx::Error::Database(Box::new(SqlState(code)));
+            let error = db_error(DbErr::Query(RuntimeErr::SqlxError(database)));
+
+            assert_eq!(variant(&error), expected, "{}", code);
+        }
+    }
+
+    #[test]
+    fn classified_errors_carry_the_database_message() {
+        let database = sqlx::Error::Database(Box::new(SqlState("23505")));
+
+        let error = db_error(DbErr::Exec(RuntimeErr::SqlxError(database)));
+
+        assert!(matches!(error, DomainError::AlreadyExists(message) if message == "message"));
+    }
+
+    #[test]
+    fn other_errors_are_classified_by_kind() {
+        let cases = [
+            (DbErr::ConnectionAcquire, "Unavailable"),
+            (DbErr::RecordNotFound("id".to_string()), "NotFound"),
+            (
+                DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::PoolTimedOut)),
+                "Unavailable",
+            ),
+            (
+                DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::RowNotFound)),
+                "Unexpected",
+            ),
+            (DbErr::Custom("custom".to_string()), "Unexpected"),
+        ];
+        for (error, expected) in cases {
+            let message = error.to_string();
+
+            assert_eq!(variant(&db_error(error)), expected, "{}", message);
+        }
+    }
+}
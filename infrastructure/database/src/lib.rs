@@ -1,32 +1,168 @@
+use crate::client::outbox::{
+    CREATE_KEYS, DELETE_KEYS, OBJECT_CREATED, OBJECT_DELETED, OBJECT_UPDATED, UPDATE_KEYS,
+};
+use crate::entities::api_keys;
+use crate::entities::dead_letters;
 use crate::entities::fields;
+use crate::entities::jobs;
+use crate::entities::keys;
 use crate::entities::objects;
+use crate::entities::outbox;
+use crate::entities::role_bindings;
 use crate::entities::rules;
-use crate::entities::sea_orm_active_enums::GenerationType;
+use crate::entities::sea_orm_active_enums::{GenerationType, JobStatus, JobType};
 use anyhow::Result;
+use domain::auth::{ApiKey, RoleBinding};
+use domain::dead_letters::DeadLetter;
 use domain::error::DomainError;
+use domain::events::{Event, ObjectChange, OutboxEvent};
 use domain::fields::Field;
+use domain::jobs::{Job, JobStatus as DomainJobStatus, JobType as DomainJobType, KeySyncJob};
+use domain::keys::Key;
 use domain::objects::Object;
 use domain::rules::{GenerationRule, Rule};
-use sea_orm::{Database, DatabaseConnection, DbErr};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr, JsonValue};
 use std::collections::HashMap;
+use std::time::Duration;
 
 pub mod client;
 pub mod entities;
+pub mod error;
+
+/// Upper bound of bind parameters PostgreSQL accepts in a single statement.
+pub const MAX_BIND_PARAMETERS: usize = 65_535;
+
+/// Connection pool settings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolOptions {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub connect_timeout: Duration,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            min_connections: 1,
+            connect_timeout: Duration::from_secs(10),
+            acquire_timeout: Duration::from_secs(30),
+            idle_timeout: Duration::from_secs(600),
+        }
+    }
+}
 
 pub async fn create_database_connection(database_url: String) -> Result<DatabaseConnection, DbErr> {
     Database::connect(&database_url).await
 }
 
+pub async fn create_database_pool(
+    database_url: String,
+    options: &PoolOptions,
+) -> Result<DatabaseConnection, DbErr> {
+    let mut connect_options = ConnectOptions::new(database_url);
+    connect_options
+        .max_connections(options.max_connections)
+        .min_connections(options.min_connections)
+        .connect_timeout(options.connect_timeout)
+        .acquire_timeout(options.acquire_timeout)
+        .idle_timeout(options.idle_timeout);
+
+    Database::connect(connect_options).await
+}
+
+impl From<api_keys::Model> for ApiKey {
+    fn from(value: api_keys::Model) -> Self {
+        ApiKey {
+            id: value.id,
+            tenant_id: value.tenant_id.into(),
+            name: value.name,
+            created_at: value.created_at,
+            revoked_at: value.revoked_at,
+        }
+    }
+}
+
+impl TryFrom<role_bindings::Model> for RoleBinding {
+    type Error = DomainError;
+
+    fn try_from(value: role_bindings::Model) -> Result<Self, Self::Error> {
+        let role = value
+            .role
+            .parse()
+            .map_err(|_| DomainError::Unexpected(format!("unknown role: {}", value.role)))?;
+
+        Ok(RoleBinding {
+            id: value.id,
+            tenant_id: value.tenant_id.into(),
+            subject: value.subject,
+            role,
+            created_at: value.created_at,
+        })
+    }
+}
+
+impl From<dead_letters::Model> for DeadLetter {
+    fn from(value: dead_letters::Model) -> Self {
+        let attributes = value
+            .attributes
+            .as_object()
+            .map(|attributes| {
+                attributes
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        DeadLetter {
+            id: value.id,
+            tenant_id: value.tenant_id.into(),
+            topic: value.topic,
+            subscription: value.subscription,
+            ordering_key: value.ordering_key,
+            data: value.data,
+            attributes,
+            error: value.error,
+            attempts: value.attempts as u32,
+            created_at: value.created_at,
+            replayed_at: value.replayed_at,
+        }
+    }
+}
+
 impl From<fields::Model> for Field {
     fn from(value: fields::Model) -> Self {
         Field {
             id: value.id,
+            tenant_id: value.tenant_id.into(),
             data_label: value.data_label,
             label: value.label,
         }
     }
 }
 
+impl From<keys::Model> for Key {
+    fn from(value: keys::Model) -> Self {
+        Key {
+            tenant_id: value.tenant_id.into(),
+            rule_id: value.rule_id,
+            object_id: value.object_id,
+            key: value.key,
+        }
+    }
+}
+
+/// Strings are read as they are rather than JSON-quoted, so that keys match the values given.
+fn attribute_value(value: JsonValue) -> String {
+    match value {
+        JsonValue::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
 pub fn to_object(
     object: objects::Model,
     fields: Vec<fields::Model>,
@@ -41,18 +177,108 @@ pub fn to_object(
         let (k, v) = lookup
             .remove_entry(&field.data_label)
             .ok_or(DomainError::Unexpected("".to_string()))?;
-        attributes.insert(k, v.to_string());
+        attributes.insert(k, attribute_value(v));
+    }
+
+    Ok(Object {
+        id: object.id,
+        tenant_id: object.tenant_id.into(),
+        attributes,
+    })
+}
+
+/// Like [`to_object`], but leaves out the fields the object does not have, e.g. when it was
+/// created before the field was added.
+pub fn to_partial_object(
+    object: objects::Model,
+    fields: Vec<fields::Model>,
+) -> Result<Object, DomainError> {
+    let mut lookup = object
+        .attributes
+        .as_object()
+        .ok_or(DomainError::Unexpected("".to_string()))?
+        .clone();
+    let mut attributes = HashMap::new();
+    for field in fields {
+        if let Some((k, v)) = lookup.remove_entry(&field.data_label) {
+            attributes.insert(k, attribute_value(v));
+        }
     }
 
     Ok(Object {
         id: object.id,
+        tenant_id: object.tenant_id.into(),
         attributes,
     })
 }
 
+impl From<jobs::Model> for Job {
+    fn from(value: jobs::Model) -> Self {
+        Job {
+            id: value.id,
+            tenant_id: value.tenant_id.into(),
+            job_type: value.r#type.into(),
+            rule_id: value.rule_id,
+            status: value.status.into(),
+            processed_objects: value.processed_objects as u64,
+            skipped_objects: value.skipped_objects as u64,
+            total_objects: value.total_objects.map(|v| v as u64),
+            error: value.error,
+            created_at: value.created_at,
+            started_at: value.started_at,
+            finished_at: value.finished_at,
+        }
+    }
+}
+
+impl From<JobType> for DomainJobType {
+    fn from(value: JobType) -> Self {
+        match value {
+            JobType::CreateKeys => DomainJobType::CreateKeys,
+            JobType::UpdateKeys => DomainJobType::UpdateKeys,
+            JobType::DeleteKeys => DomainJobType::DeleteKeys,
+        }
+    }
+}
+
+impl From<DomainJobType> for JobType {
+    fn from(value: DomainJobType) -> Self {
+        match value {
+            DomainJobType::CreateKeys => JobType::CreateKeys,
+            DomainJobType::UpdateKeys => JobType::UpdateKeys,
+            DomainJobType::DeleteKeys => JobType::DeleteKeys,
+        }
+    }
+}
+
+impl From<JobStatus> for DomainJobStatus {
+    fn from(value: JobStatus) -> Self {
+        match value {
+            JobStatus::Pending => DomainJobStatus::Pending,
+            JobStatus::Running => DomainJobStatus::Running,
+            JobStatus::Succeeded => DomainJobStatus::Succeeded,
+            JobStatus::Failed => DomainJobStatus::Failed,
+            JobStatus::Cancelled => DomainJobStatus::Cancelled,
+        }
+    }
+}
+
+impl From<DomainJobStatus> for JobStatus {
+    fn from(value: DomainJobStatus) -> Self {
+        match value {
+            DomainJobStatus::Pending => JobStatus::Pending,
+            DomainJobStatus::Running => JobStatus::Running,
+            DomainJobStatus::Succeeded => JobStatus::Succeeded,
+            DomainJobStatus::Failed => JobStatus::Failed,
+            DomainJobStatus::Cancelled => JobStatus::Cancelled,
+        }
+    }
+}
+
 pub fn to_rule(rule: rules::Model, field: fields::Model) -> Rule {
     Rule {
         id: rule.id,
+        tenant_id: rule.tenant_id.into(),
         field: field.into(),
         rule_type: match rule.r#type {
             GenerationType::Equals => GenerationRule::Equals,
@@ -61,5 +287,104 @@ pub fn to_rule(rule: rules::Model, field: fields::Model) -> Rule {
                 replacer: rule.regex_replacer.unwrap_or("".to_string()),
             },
         },
+        version: rule.version,
+    }
+}
+
+pub fn to_outbox_event(event: outbox::Model) -> Result<OutboxEvent, DomainError> {
+    let id = event.aggregate_id;
+    let version = event.aggregate_version;
+    let payload = match event.event_type.as_str() {
+        CREATE_KEYS => Event::KeySync(KeySyncJob::Create {
+            rule_id: id,
+            rule_version: version,
+        }),
+        UPDATE_KEYS => Event::KeySync(KeySyncJob::Update {
+            rule_id: id,
+            rule_version: version,
+        }),
+        DELETE_KEYS => Event::KeySync(KeySyncJob::Delete {
+            rule_id: id,
+            rule_version: version,
+        }),
+        OBJECT_CREATED => Event::ObjectChanged {
+            object_id: id,
+            change: ObjectChange::Created,
+        },
+        OBJECT_UPDATED => Event::ObjectChanged {
+            object_id: id,
+            change: ObjectChange::Updated,
+        },
+        OBJECT_DELETED => Event::ObjectChanged {
+            object_id: id,
+            change: ObjectChange::Deleted,
+        },
+        v => {
+            return Err(DomainError::Unexpected(format!(
+                "Unknown event type: [{}]",
+                v
+            )))
+        }
+    };
+
+    Ok(OutboxEvent {
+        sequence: event.sequence,
+        event_id: event.event_id,
+        tenant_id: event.tenant_id.into(),
+        event: payload,
+        request_id: event.request_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::prelude::DateTimeWithTimeZone;
+
+    fn object(attributes: &str) -> objects::Model {
+        objects::Model {
+            id: "object".to_string(),
+            attributes: attributes.parse().unwrap(),
+            created_at: DateTimeWithTimeZone::default(),
+            updated_at: DateTimeWithTimeZone::default(),
+            source: None,
+            external_id: None,
+            tenant_id: "default".to_string(),
+        }
+    }
+
+    fn field(data_label: &str) -> fields::Model {
+        fields::Model {
+            id: data_label.to_string(),
+            data_label: data_label.to_string(),
+            label: data_label.to_string(),
+            created_at: DateTimeWithTimeZone::default(),
+            updated_at: DateTimeWithTimeZone::default(),
+            tenant_id: "default".to_string(),
+        }
+    }
+
+    #[test]
+    fn attributes_are_not_json_quoted() {
+        let object = to_object(
+            object(r#"{"phone": "222", "age": 20}"#),
+            vec![field("phone"), field("age")],
+        )
+        .unwrap();
+
+        assert_eq!(object.attributes["phone"], "222");
+        assert_eq!(object.attributes["age"], "20");
+    }
+
+    #[test]
+    fn missing_attributes_fail_unless_partial() {
+        let fields = vec![field("phone"), field("email")];
+
+        assert!(to_object(object(r#"{"phone": "222"}"#), fields.clone()).is_err());
+        let object = to_partial_object(object(r#"{"phone": "222"}"#), fields).unwrap();
+        assert_eq!(
+            object.attributes,
+            HashMap::from([("phone".to_string(), "222".to_string())])
+        );
     }
 }
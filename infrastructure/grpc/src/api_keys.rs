@@ -0,0 +1,106 @@
+use crate::api::api_keys_server::ApiKeys;
+use crate::api::{
+    ApiKey, CreateApiKeyRequest, CreateApiKeyResponse, ListApiKeysRequest, ListApiKeysResponse,
+    RevokeApiKeyRequest, RevokeApiKeyResponse,
+};
+use crate::auth::api_key::{generate_secret, hash_secret};
+use crate::auth::authorize;
+use crate::error::handle_error;
+use crate::tenants::tenant;
+use crate::timestamp::to_timestamp;
+use domain::auth::{ApiKey as ApiKeyModel, ApiKeyRepository, Permission, Principal};
+use domain::error::DomainError;
+use tonic::{Request, Response, Status};
+
+#[derive(Debug)]
+pub struct ApiKeysServerImpl<T: ApiKeyRepository> {
+    repository: T,
+}
+
+impl<T: ApiKeyRepository> ApiKeysServerImpl<T> {
+    pub fn new(repository: T) -> Self {
+        Self { repository }
+    }
+}
+
+#[tonic::async_trait]
+impl<T: ApiKeyRepository> ApiKeys for ApiKeysServerImpl<T> {
+    async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, Status> {
+        authorize(&request, Permission::AuthAdmin).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        if request.name.is_empty() {
+            return Err(handle_error(DomainError::InvalidArgument(
+                "name must not be empty".to_string(),
+            )));
+        }
+
+        let secret = generate_secret();
+        let result = repository
+            .create(request.name, hash_secret(&secret))
+            .await
+            .map_err(handle_error)?;
+        tracing::info!(api_key_id = %result.id, "created API key");
+
+        let response = CreateApiKeyResponse {
+            api_key: Some(result.into()),
+            secret,
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ListApiKeysResponse>, Status> {
+        authorize(&request, Permission::AuthAdmin).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let result = repository.find_all().await.map_err(handle_error)?;
+
+        let response = ListApiKeysResponse {
+            api_keys: result.into_iter().map(Into::into).collect::<Vec<ApiKey>>(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<RevokeApiKeyResponse>, Status> {
+        authorize(&request, Permission::AuthAdmin).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository.revoke(request.id).await.map_err(handle_error)?;
+        tracing::info!(api_key_id = %result.id, "revoked API key");
+
+        let response = RevokeApiKeyResponse {
+            api_key: Some(result.into()),
+        };
+
+        Ok(Response::new(response))
+    }
+}
+
+impl From<ApiKeyModel> for ApiKey {
+    fn from(value: ApiKeyModel) -> Self {
+        let subject = Principal::api_key(&value).subject;
+        ApiKey {
+            id: value.id,
+            name: value.name,
+            created_at: Some(to_timestamp(value.created_at)),
+            revoked_at: value.revoked_at.map(to_timestamp),
+            subject,
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use crate::error::handle_error;
+use crate::tenants;
+use domain::auth::{Permission, Principal, RoleBindingRepository};
+use domain::error::DomainError;
+use domain::tenants::TenantId;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::http::{HeaderMap, Request, Response};
+use tower::{Layer, Service};
+
+pub mod api_key;
+pub mod jwt;
+
+/// Services callable without credentials, so that probes and tooling keep working.
+const PUBLIC_PATH_PREFIXES: [&str; 2] = ["/grpc.health.v1.Health/", "/grpc.reflection."];
+
+/// Turns the credentials in the request metadata into a [`Principal`].
+#[tonic::async_trait]
+pub trait Authenticator: Send + Sync + 'static {
+    /// Returns `Ok(None)` when the request carries no credentials of this kind, and
+    /// `Unauthenticated` when it carries invalid ones.
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<Option<Principal>, DomainError>;
+}
+
+/// Roles bound to a subject in Postgres, object-safe so that [`AuthLayer`] needs no type
+/// parameter.
+#[tonic::async_trait]
+trait BoundRoles: Send + Sync + 'static {
+    async fn bound_roles(
+        &self,
+        tenant_id: &TenantId,
+        subject: &str,
+    ) -> Result<Vec<String>, DomainError>;
+}
+
+#[tonic::async_trait]
+impl<T: RoleBindingRepository> BoundRoles for T {
+    async fn bound_roles(
+        &self,
+        tenant_id: &TenantId,
+        subject: &str,
+    ) -> Result<Vec<String>, DomainError> {
+        let role_bindings = self
+            .for_tenant(tenant_id)
+            .find_all(Some(subject.to_string()))
+            .await?;
+
+        Ok(role_bindings
+            .into_iter()
+            .map(|binding| binding.role.as_str().to_string())
+            .collect())
+    }
+}
+
+struct Auth {
+    authenticators: Vec<Box<dyn Authenticator>>,
+    role_bindings: Box<dyn BoundRoles>,
+}
+
+/// Authenticates every RPC with the first authenticator that recognizes its credentials,
+/// resolves its tenant with [`tenants::resolve`], adds the roles bound to the subject in that
+/// tenant and attaches the [`Principal`] and [`TenantId`] to the request extensions and to the
+/// `rpc` span. When disabled, every RPC is made by [`Principal::anonymous`].
+#[derive(Clone)]
+pub struct AuthLayer {
+    auth: Option<Arc<Auth>>,
+}
+
+impl AuthLayer {
+    pub fn new<T: RoleBindingRepository>(
+        authenticators: Vec<Box<dyn Authenticator>>,
+        role_bindings: T,
+    ) -> Self {
+        Self {
+            auth: Some(Arc::new(Auth {
+                authenticators,
+                role_bindings: Box::new(role_bindings),
+            })),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self { auth: None }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            auth: self.auth.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    auth: Option<Arc<Auth>>,
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for AuthService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let path = request.uri().path();
+        let public = PUBLIC_PATH_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix));
+        let auth = match (&self.auth, public) {
+            (Some(auth), false) => auth.clone(),
+            (None, _) => {
+                let principal = Principal::anonymous();
+                let tenant_id = match tenants::resolve(&principal, request.headers()) {
+                    Ok(tenant_id) => tenant_id,
+                    Err(e) => return Box::pin(async move { Ok(handle_error(e).to_http()) }),
+                };
+                tracing::Span::current().record("tenant_id", tenant_id.as_str());
+                request.extensions_mut().insert(principal);
+                request.extensions_mut().insert(tenant_id);
+                return Box::pin(self.inner.call(request));
+            }
+            (_, true) => return Box::pin(self.inner.call(request)),
+        };
+
+        // the ready service is taken, the clone is polled for readiness by the next call
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            let headers = request.headers();
+            let (principal, tenant_id) = match auth.authenticate(headers).await {
+                Ok(authenticated) => authenticated,
+                Err(e) => return Ok(handle_error(e).to_http()),
+            };
+            let span = tracing::Span::current();
+            span.record("principal", principal.subject.as_str());
+            span.record("tenant_id", tenant_id.as_str());
+            request.extensions_mut().insert(principal);
+            request.extensions_mut().insert(tenant_id);
+
+            inner.call(request).await
+        })
+    }
+}
+
+impl Auth {
+    async fn authenticate(
+        &self,
+        headers: &HeaderMap,
+    ) -> Result<(Principal, TenantId), DomainError> {
+        for authenticator in &self.authenticators {
+            if let Some(mut principal) = authenticator.authenticate(headers).await? {
+                let tenant_id = tenants::resolve(&principal, headers)?;
+                let bound_roles = self
+                    .role_bindings
+                    .bound_roles(&tenant_id, &principal.subject)
+                    .await?;
+                for role in bound_roles {
+                    if !principal.roles.contains(&role) {
+                        principal.roles.push(role);
+                    }
+                }
+
+                return Ok((principal, tenant_id));
+            }
+        }
+
+        Err(DomainError::Unauthenticated(format!(
+            "credentials are required in the [{}] or [authorization] metadata",
+            api_key::HEADER
+        )))
+    }
+}
+
+/// Returns the caller attached by [`AuthLayer`].
+pub fn principal<T>(request: &tonic::Request<T>) -> Result<&Principal, DomainError> {
+    request
+        .extensions()
+        .get::<Principal>()
+        .ok_or_else(|| DomainError::Unauthenticated("the request is not authenticated".to_string()))
+}
+
+/// Fails with `PermissionDenied` unless a role of the caller grants `permission`.
+pub fn authorize<T>(
+    request: &tonic::Request<T>,
+    permission: Permission,
+) -> Result<(), DomainError> {
+    let principal = principal(request)?;
+    if !principal.has_permission(permission) {
+        return Err(DomainError::PermissionDenied(format!(
+            "[{}] does not have the [{}] permission",
+            principal.subject,
+            permission.as_str()
+        )));
+    }
+
+    Ok(())
+}
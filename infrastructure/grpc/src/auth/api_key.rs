@@ -0,0 +1,165 @@
+use crate::auth::Authenticator;
+use domain::auth::{ApiKeyRepository, Principal};
+use domain::error::DomainError;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use tonic::codegen::http::HeaderMap;
+
+/// Metadata carrying the secret of an API key.
+pub const HEADER: &str = "x-api-key";
+/// Makes secrets easy to spot, e.g. by secret scanners.
+const SECRET_PREFIX: &str = "ppp_";
+const SECRET_LEN: usize = 40;
+
+/// Returns a new random secret. Only its [`hash_secret`] is stored.
+pub fn generate_secret() -> String {
+    let secret: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(SECRET_LEN)
+        .map(char::from)
+        .collect();
+
+    format!("{}{}", SECRET_PREFIX, secret)
+}
+
+/// Hex-encoded SHA-256 of `secret`. Secrets are random, so they need no salt.
+pub fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// Authenticates the `x-api-key` metadata against the keys that have not been revoked.
+#[derive(Debug)]
+pub struct ApiKeyAuthenticator<T: ApiKeyRepository> {
+    repository: T,
+}
+
+impl<T: ApiKeyRepository> ApiKeyAuthenticator<T> {
+    pub fn new(repository: T) -> Self {
+        Self { repository }
+    }
+}
+
+#[tonic::async_trait]
+impl<T: ApiKeyRepository> Authenticator for ApiKeyAuthenticator<T> {
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<Option<Principal>, DomainError> {
+        let Some(secret) = headers.get(HEADER) else {
+            return Ok(None);
+        };
+        let secret = secret
+            .to_str()
+            .map_err(|_| DomainError::Unauthenticated("invalid API key".to_string()))?;
+        let api_key = self
+            .repository
+            .find_active_by_hash(hash_secret(secret))
+            .await?
+            .ok_or_else(|| DomainError::Unauthenticated("invalid API key".to_string()))?;
+
+        Ok(Some(Principal::api_key(&api_key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use domain::auth::{ApiKey, AuthMethod};
+    use domain::tenants::{TenantId, TenantScoped};
+
+    /// Finds keys by the hash of their id, as their secret.
+    #[derive(Clone)]
+    struct Keys(Vec<ApiKey>);
+
+    impl TenantScoped for Keys {
+        fn for_tenant(&self, _: &TenantId) -> Self {
+            self.clone()
+        }
+    }
+
+    #[tonic::async_trait]
+    impl ApiKeyRepository for Keys {
+        async fn find_active_by_hash(
+            &self,
+            key_hash: String,
+        ) -> Result<Option<ApiKey>, DomainError> {
+            Ok(self
+                .0
+                .iter()
+                .find(|key| hash_secret(&key.id) == key_hash && key.revoked_at.is_none())
+                .cloned())
+        }
+
+        async fn find_all(&self) -> Result<Vec<ApiKey>, DomainError> {
+            unimplemented!()
+        }
+
+        async fn create(&self, _: String, _: String) -> Result<ApiKey, DomainError> {
+            unimplemented!()
+        }
+
+        async fn revoke(&self, _: String) -> Result<ApiKey, DomainError> {
+            unimplemented!()
+        }
+    }
+
+    fn key(id: &str, revoked: bool) -> ApiKey {
+        ApiKey {
+            id: id.to_string(),
+            tenant_id: TenantId::parse("acme").unwrap(),
+            name: id.to_string(),
+            created_at: Utc::now().into(),
+            revoked_at: revoked.then(|| Utc::now().into()),
+        }
+    }
+
+    async fn authenticate(secret: Option<&str>) -> Result<Option<Principal>, DomainError> {
+        let authenticator =
+            ApiKeyAuthenticator::new(Keys(vec![key("active", false), key("revoked", true)]));
+        let mut headers = HeaderMap::new();
+        if let Some(secret) = secret {
+            headers.insert(HEADER, secret.parse().unwrap());
+        }
+
+        authenticator.authenticate(&headers).await
+    }
+
+    #[tokio::test]
+    async fn active_keys_authenticate_as_their_tenant() {
+        let principal = authenticate(Some("active")).await.unwrap().unwrap();
+
+        assert_eq!(principal.subject, "api-key:active");
+        assert_eq!(principal.method, AuthMethod::ApiKey);
+        assert_eq!(principal.tenant_id, Some(TenantId::parse("acme").unwrap()));
+    }
+
+    #[tokio::test]
+    async fn missing_keys_are_left_to_other_authenticators() {
+        assert!(authenticate(None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn revoked_and_unknown_keys_are_unauthenticated() {
+        for secret in ["revoked", "unknown", "Active"] {
+            let result = authenticate(Some(secret)).await;
+
+            assert!(
+                matches!(result, Err(DomainError::Unauthenticated(_))),
+                "{}",
+                secret
+            );
+        }
+    }
+
+    #[test]
+    fn secrets_are_prefixed_and_hashed_to_hex() {
+        let secret = generate_secret();
+
+        assert!(secret.starts_with(SECRET_PREFIX));
+        assert_eq!(secret.len(), SECRET_PREFIX.len() + SECRET_LEN);
+        assert_ne!(generate_secret(), secret);
+        assert_eq!(
+            hash_secret("secret"),
+            "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b"
+        );
+    }
+}
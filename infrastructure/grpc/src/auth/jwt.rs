@@ -0,0 +1,241 @@
+use crate::auth::Authenticator;
+use anyhow::{Context, Result};
+use domain::auth::{AuthMethod, Principal};
+use domain::error::DomainError;
+use domain::tenants::TenantId;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use tonic::codegen::http::header::AUTHORIZATION;
+use tonic::codegen::http::HeaderMap;
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    #[serde(default)]
+    roles: Vec<String>,
+    tenant_id: Option<String>,
+}
+
+/// Verifies `authorization: Bearer <jwt>` metadata against the keys of a JWKS.
+/// Tokens must be signed with the algorithm of the key named by their `kid`, which may be
+/// omitted when the set has a single key, and carry `sub` and `exp` claims. A `tenant_id`
+/// claim confines the caller to that tenant.
+#[derive(Debug)]
+pub struct JwtAuthenticator {
+    keys: JwkSet,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl JwtAuthenticator {
+    pub fn new(keys: JwkSet, issuer: Option<String>, audience: Option<String>) -> Self {
+        Self {
+            keys,
+            issuer,
+            audience,
+        }
+    }
+
+    pub fn from_file(
+        path: &Path,
+        issuer: Option<String>,
+        audience: Option<String>,
+    ) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read JWKS file: [{}]", path.display()))?;
+        let keys = serde_json::from_str(&content)
+            .with_context(|| format!("invalid JWKS file: [{}]", path.display()))?;
+
+        Ok(Self::new(keys, issuer, audience))
+    }
+
+    fn verify(&self, token: &str) -> Result<Principal, DomainError> {
+        let header = decode_header(token).map_err(invalid_token)?;
+        let jwk = match &header.kid {
+            Some(kid) => self.keys.find(kid),
+            None if self.keys.keys.len() == 1 => self.keys.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| DomainError::Unauthenticated("unknown signing key".to_string()))?;
+        let key = DecodingKey::from_jwk(jwk).map_err(invalid_token)?;
+
+        let mut validation = Validation::new(jwk.common.algorithm.unwrap_or(header.alg));
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if let Some(audience) = &self.audience {
+            validation.set_audience(&[audience]);
+        }
+        let claims = decode::<Claims>(token, &key, &validation)
+            .map_err(invalid_token)?
+            .claims;
+        let tenant_id = claims
+            .tenant_id
+            .map(|tenant_id| TenantId::parse(&tenant_id))
+            .transpose()
+            .map_err(|_| DomainError::Unauthenticated("invalid tenant_id claim".to_string()))?;
+
+        Ok(Principal {
+            subject: claims.sub,
+            method: AuthMethod::Jwt,
+            roles: claims.roles,
+            tenant_id,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl Authenticator for JwtAuthenticator {
+    async fn authenticate(&self, headers: &HeaderMap) -> Result<Option<Principal>, DomainError> {
+        let Some(authorization) = headers.get(AUTHORIZATION) else {
+            return Ok(None);
+        };
+        let token = authorization
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+            .ok_or_else(|| DomainError::Unauthenticated("expected a bearer token".to_string()))?;
+
+        self.verify(token).map(Some)
+    }
+}
+
+fn invalid_token(error: jsonwebtoken::errors::Error) -> DomainError {
+    DomainError::Unauthenticated(format!("invalid token: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    use serde_json::{json, Value};
+
+    /// Base64 of `secret-key-for-tests-000`.
+    const SECRET: &str = "c2VjcmV0LWtleS1mb3ItdGVzdHMtMDAw";
+    /// Base64 of `other-key-for-tests-0000`.
+    const OTHER_SECRET: &str = "b3RoZXIta2V5LWZvci10ZXN0cy0wMDAw";
+    const ISSUER: &str = "https://issuer.example.com";
+    const AUDIENCE: &str = "poo-pad-pong";
+
+    fn authenticator() -> JwtAuthenticator {
+        let keys = serde_json::from_value(json!({
+            "keys": [{ "kty": "oct", "kid": "key", "alg": "HS256", "k": SECRET }]
+        }))
+        .unwrap();
+
+        JwtAuthenticator::new(keys, Some(ISSUER.to_string()), Some(AUDIENCE.to_string()))
+    }
+
+    fn claims() -> Value {
+        json!({
+            "sub": "user",
+            "roles": ["viewer"],
+            "tenant_id": "acme",
+            "iss": ISSUER,
+            "aud": AUDIENCE,
+            "exp": Utc::now().timestamp() + 3600,
+        })
+    }
+
+    fn token(kid: &str, secret: &str, claims: &Value) -> String {
+        let header = Header {
+            kid: Some(kid.to_string()),
+            ..Header::new(Algorithm::HS256)
+        };
+
+        encode(
+            &header,
+            claims,
+            &EncodingKey::from_base64_secret(secret).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn valid_tokens_name_the_principal() {
+        let principal = authenticator()
+            .verify(&token("key", SECRET, &claims()))
+            .unwrap();
+
+        assert_eq!(principal.subject, "user");
+        assert_eq!(principal.method, AuthMethod::Jwt);
+        assert_eq!(principal.roles, vec!["viewer"]);
+        assert_eq!(principal.tenant_id, Some(TenantId::parse("acme").unwrap()));
+    }
+
+    #[test]
+    fn invalid_tokens_are_unauthenticated() {
+        let with = |key: &str, value: Value| {
+            let mut claims = claims();
+            claims[key] = value;
+            claims
+        };
+        let cases = [
+            (
+                "expired",
+                token(
+                    "key",
+                    SECRET,
+                    &with("exp", json!(Utc::now().timestamp() - 3600)),
+                ),
+            ),
+            ("unknown kid", token("other", SECRET, &claims())),
+            ("bad signature", token("key", OTHER_SECRET, &claims())),
+            (
+                "wrong audience",
+                token("key", SECRET, &with("aud", json!("other"))),
+            ),
+            (
+                "wrong issuer",
+                token("key", SECRET, &with("iss", json!("other"))),
+            ),
+            (
+                "invalid tenant",
+                token("key", SECRET, &with("tenant_id", json!("Not A Tenant"))),
+            ),
+            ("malformed", "not-a-token".to_string()),
+        ];
+        for (name, token) in cases {
+            let result = authenticator().verify(&token);
+
+            assert!(
+                matches!(result, Err(DomainError::Unauthenticated(_))),
+                "{}: {:?}",
+                name,
+                result
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn only_bearer_tokens_are_verified() {
+        let authenticator = authenticator();
+        let mut headers = HeaderMap::new();
+        assert!(authenticator
+            .authenticate(&headers)
+            .await
+            .unwrap()
+            .is_none());
+
+        let token = token("key", SECRET, &claims());
+        headers.insert(AUTHORIZATION, format!("Basic {}", token).parse().unwrap());
+        assert!(matches!(
+            authenticator.authenticate(&headers).await,
+            Err(DomainError::Unauthenticated(_))
+        ));
+
+        headers.insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        assert!(authenticator
+            .authenticate(&headers)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
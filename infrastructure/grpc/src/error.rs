@@ -1,9 +1,157 @@
+use crate::rpc::bad_request::FieldViolation;
+use crate::rpc::precondition_failure::Violation;
+use crate::rpc::{BadRequest, ErrorInfo, PreconditionFailure, ResourceInfo, RetryInfo};
 use domain::error::DomainError;
+use prost::Message;
+use prost_types::Any;
+use std::collections::HashMap;
 use tonic::{Code, Status};
 
+/// `ErrorInfo.domain` of the errors of this service.
+const ERROR_DOMAIN: &str = "poo-pad-pong";
+/// Delay suggested to clients retrying a conflicting or unavailable request.
+const RETRY_DELAY_SECS: i64 = 1;
+/// Message of the unexpected errors sent to clients.
+const INTERNAL_MESSAGE: &str = "internal error";
+
+/// Converts `error` to a status with `google.rpc` error details: an `ErrorInfo` naming the
+/// reason, and a `ResourceInfo`, `BadRequest`, `PreconditionFailure` or `RetryInfo` by code.
 pub fn handle_error(error: DomainError) -> Status {
-    match error {
-        DomainError::NotFound(e) => Status::new(Code::NotFound, e),
-        DomainError::Unexpected(e) => Status::new(Code::Internal, e),
+    let (code, reason, message, detail) = match error {
+        DomainError::NotFound(e) => (
+            Code::NotFound,
+            "NOT_FOUND",
+            e.clone(),
+            Some(resource_info(e)),
+        ),
+        DomainError::InvalidArgument(e) => (
+            Code::InvalidArgument,
+            "INVALID_ARGUMENT",
+            e.clone(),
+            Some(pack(
+                "BadRequest",
+                BadRequest {
+                    field_violations: vec![FieldViolation {
+                        field: String::new(),
+                        description: e,
+                    }],
+                },
+            )),
+        ),
+        DomainError::AlreadyExists(e) => (
+            Code::AlreadyExists,
+            "ALREADY_EXISTS",
+            e.clone(),
+            Some(resource_info(e)),
+        ),
+        DomainError::FailedPrecondition(e) => (
+            Code::FailedPrecondition,
+            "FAILED_PRECONDITION",
+            e.clone(),
+            Some(pack(
+                "PreconditionFailure",
+                PreconditionFailure {
+                    violations: vec![Violation {
+                        r#type: String::new(),
+                        subject: String::new(),
+                        description: e,
+                    }],
+                },
+            )),
+        ),
+        DomainError::Unauthenticated(e) => (Code::Unauthenticated, "UNAUTHENTICATED", e, None),
+        DomainError::PermissionDenied(e) => (Code::PermissionDenied, "PERMISSION_DENIED", e, None),
+        DomainError::Conflict(e) => (Code::Aborted, "CONFLICT", e, Some(retry_info())),
+        DomainError::Unavailable(e) => {
+            tracing::warn!(error = %e, "dependency unavailable");
+            (Code::Unavailable, "UNAVAILABLE", e, Some(retry_info()))
+        }
+        // the cause may expose internals, so it is only logged
+        DomainError::Unexpected(e) => {
+            tracing::error!(error = %e, "unexpected error");
+            (
+                Code::Internal,
+                "INTERNAL",
+                INTERNAL_MESSAGE.to_string(),
+                None,
+            )
+        }
+    };
+
+    let mut details = vec![pack(
+        "ErrorInfo",
+        ErrorInfo {
+            reason: reason.to_string(),
+            domain: ERROR_DOMAIN.to_string(),
+            metadata: HashMap::new(),
+        },
+    )];
+    details.extend(detail);
+    let status = crate::rpc::Status {
+        code: code as i32,
+        message: message.clone(),
+        details,
+    };
+
+    Status::with_details(code, message, status.encode_to_vec().into())
+}
+
+fn resource_info(description: String) -> Any {
+    pack(
+        "ResourceInfo",
+        ResourceInfo {
+            description,
+            ..Default::default()
+        },
+    )
+}
+
+fn retry_info() -> Any {
+    pack(
+        "RetryInfo",
+        RetryInfo {
+            retry_delay: Some(prost_types::Duration {
+                seconds: RETRY_DELAY_SECS,
+                nanos: 0,
+            }),
+        },
+    )
+}
+
+fn pack<M: Message>(name: &str, message: M) -> Any {
+    Any {
+        type_url: format!("type.googleapis.com/google.rpc.{}", name),
+        value: message.encode_to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unexpected_errors_are_not_sent_to_clients() {
+        let status = handle_error(DomainError::Unexpected("password=secret".to_string()));
+
+        assert_eq!(status.code(), Code::Internal);
+        assert_eq!(status.message(), INTERNAL_MESSAGE);
+        let details = crate::rpc::Status::decode(status.details()).unwrap();
+        assert_eq!(details.message, INTERNAL_MESSAGE);
+    }
+
+    #[test]
+    fn invalid_arguments_name_the_violation() {
+        let status = handle_error(DomainError::InvalidArgument(
+            "rule_type must be set".to_string(),
+        ));
+
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "rule_type must be set");
+        let details = crate::rpc::Status::decode(status.details()).unwrap();
+        let bad_request = BadRequest::decode(details.details[1].value.as_slice()).unwrap();
+        assert_eq!(
+            bad_request.field_violations[0].description,
+            "rule_type must be set"
+        );
     }
 }
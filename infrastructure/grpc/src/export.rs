@@ -0,0 +1,61 @@
+use crate::error::handle_error;
+use domain::error::DomainError;
+use std::future::Future;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Status;
+
+const DEFAULT_BATCH_SIZE: u64 = 500;
+const MAX_BATCH_SIZE: u64 = 5_000;
+
+pub type ExportStream<T> = ReceiverStream<Result<T, Status>>;
+
+pub fn batch_size(requested: u32) -> u64 {
+    match u64::from(requested) {
+        0 => DEFAULT_BATCH_SIZE,
+        v => v.min(MAX_BATCH_SIZE),
+    }
+}
+
+/// Streams the rows returned by `fetch` batch by batch.
+///
+/// `fetch` receives the cursor of the last row sent so far. The channel only holds one batch,
+/// so the next batch is not read from the database until the client has caught up.
+pub fn export<T, C, F, Fut>(batch_size: u64, fetch: F) -> ExportStream<T>
+where
+    T: Send + 'static,
+    C: Send + 'static,
+    F: Fn(Option<C>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Vec<(T, C)>, DomainError>> + Send,
+{
+    let (tx, rx) = mpsc::channel(batch_size as usize);
+
+    tokio::spawn(async move {
+        let mut after = None;
+        loop {
+            let rows = fetch(after.take());
+            let rows = match rows.await {
+                Ok(rows) => rows,
+                Err(e) => {
+                    let _ = tx.send(Err(handle_error(e))).await;
+                    return;
+                }
+            };
+            let is_last = (rows.len() as u64) < batch_size;
+
+            for (row, cursor) in rows {
+                if tx.send(Ok(row)).await.is_err() {
+                    // the client has gone away
+                    return;
+                }
+                after = Some(cursor);
+            }
+
+            if is_last {
+                return;
+            }
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
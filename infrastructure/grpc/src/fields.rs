@@ -3,7 +3,10 @@ use crate::api::{
     AddFieldRequest, AddFieldResponse, Field, GetFieldsRequest, GetFieldsResponse,
     UpdateFieldRequest, UpdateFieldResponse,
 };
+use crate::auth::authorize;
 use crate::error::handle_error;
+use crate::tenants::tenant;
+use domain::auth::Permission;
 use domain::fields::{Field as FieldModel, FieldRepository};
 use tonic::{Request, Response, Status};
 
@@ -22,9 +25,13 @@ impl<T: FieldRepository> FieldsServerImpl<T> {
 impl<T: FieldRepository> Fields for FieldsServerImpl<T> {
     async fn get_fields(
         &self,
-        _request: Request<GetFieldsRequest>,
+        request: Request<GetFieldsRequest>,
     ) -> Result<Response<GetFieldsResponse>, Status> {
-        let result = self.repository.find_all().await.map_err(handle_error)?;
+        authorize(&request, Permission::FieldsRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let result = repository.find_all().await.map_err(handle_error)?;
 
         let response = GetFieldsResponse {
             fields: result.into_iter().map(Into::into).collect::<Vec<Field>>(),
@@ -37,9 +44,12 @@ impl<T: FieldRepository> Fields for FieldsServerImpl<T> {
         &self,
         request: Request<AddFieldRequest>,
     ) -> Result<Response<AddFieldResponse>, Status> {
-        let request = request.into_inner();
-        let result = self
+        authorize(&request, Permission::FieldsWrite).map_err(handle_error)?;
+        let repository = self
             .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository
             .create(request.data_label, request.label)
             .await
             .map_err(handle_error)?;
@@ -54,9 +64,12 @@ impl<T: FieldRepository> Fields for FieldsServerImpl<T> {
         &self,
         request: Request<UpdateFieldRequest>,
     ) -> Result<Response<UpdateFieldResponse>, Status> {
-        let request = request.into_inner();
-        let result = self
+        authorize(&request, Permission::FieldsWrite).map_err(handle_error)?;
+        let repository = self
             .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository
             .update(request.id, request.label)
             .await
             .map_err(handle_error)?;
@@ -0,0 +1,900 @@
+use crate::auth::api_key;
+use crate::error::handle_error;
+use crate::tenants;
+use crate::tls::TlsConnectInfo;
+use anyhow::{anyhow, bail, Context, Result};
+use domain::error::DomainError;
+use http_body::{Body as _, Limited};
+use hyper::server::accept;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Server};
+use percent_encoding::percent_decode_str;
+use prost_reflect::prost::Message as _;
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, FieldDescriptor, Kind, MessageDescriptor,
+    MethodDescriptor, SerializeOptions,
+};
+use serde_json::{json, Map, Value};
+use std::convert::Infallible;
+use std::sync::Arc;
+use telemetry::request_id;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_stream::Stream;
+use tokio_util::sync::CancellationToken;
+use tonic::body::BoxBody;
+use tonic::codegen::http::header::{CONTENT_TYPE, TE};
+use tonic::codegen::http::{HeaderValue, Method, Request, Response, StatusCode, Version};
+use tonic::codegen::StdError;
+use tonic::transport::server::{Connected, TcpConnectInfo};
+use tonic::{Code, Status};
+use tower::util::BoxCloneService;
+use tower::{Service, ServiceExt};
+
+mod openapi;
+
+/// Serves the OpenAPI document of the gateway.
+pub const OPENAPI_PATH: &str = "/openapi.json";
+/// Largest request body accepted, which is the largest message accepted by gRPC.
+const MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+/// Metadata passed on to the RPC.
+const FORWARDED_HEADERS: [&str; 4] = [
+    api_key::HEADER,
+    "authorization",
+    tenants::HEADER,
+    request_id::HEADER,
+];
+/// Messages accepted as strings in JSON, and so in paths and query strings.
+const STRING_MESSAGES: [&str; 4] = [
+    "google.protobuf.Timestamp",
+    "google.protobuf.Duration",
+    "google.protobuf.FieldMask",
+    "google.protobuf.StringValue",
+];
+
+type GrpcService = BoxCloneService<Request<Body>, Response<BoxBody>, StdError>;
+
+/// Serves the unary RPCs annotated with `google.api.http` as JSON over HTTP.
+/// Requests are transcoded to gRPC and handled by the same services and layers as gRPC
+/// requests, so authentication, tenants, errors, traces and metrics are the same. Messages
+/// use the proto3 JSON mapping and errors are `google.rpc.Status` messages.
+#[derive(Clone)]
+pub struct Gateway {
+    routes: Arc<[Route]>,
+    openapi: Arc<str>,
+    status_message: MessageDescriptor,
+    grpc: GrpcService,
+}
+
+/// A method bound to an HTTP method and path template by its `google.api.http` option.
+struct Route {
+    method: Method,
+    template: PathTemplate,
+    /// Path of the RPC, e.g. `/api.Objects/GetObject`.
+    grpc_path: String,
+    /// Field of the request the body is read into, `*` for the whole request, or `None`
+    /// when the fields not bound by the path are read from the query string.
+    body: Option<String>,
+    rpc: MethodDescriptor,
+    input: MessageDescriptor,
+}
+
+/// `/v1/objects/{id}` or `/v1/jobs/{id}:cancel`. Variables match a single segment.
+struct PathTemplate {
+    segments: Vec<Segment>,
+    verb: Option<String>,
+}
+
+enum Segment {
+    Literal(String),
+    Variable(String),
+}
+
+impl Gateway {
+    /// Routes the methods of `descriptor_set` that have a `google.api.http` option to `grpc`.
+    pub fn new<S>(descriptor_set: &[u8], grpc: S) -> Result<Self>
+    where
+        S: Service<Request<Body>, Response = Response<BoxBody>> + Clone + Send + 'static,
+        S::Future: Send + 'static,
+        S::Error: Into<StdError>,
+    {
+        let (routes, status_message) = routes(descriptor_set)?;
+        let openapi = openapi::document(&routes, &status_message);
+        let grpc = BoxCloneService::new(grpc.map_err(Into::into));
+
+        Ok(Self {
+            routes: routes.into(),
+            openapi: serde_json::to_string_pretty(&openapi)?.into(),
+            status_message,
+            grpc,
+        })
+    }
+
+    /// Serves the gateway on the connections of `incoming` until `shutdown` is cancelled.
+    pub async fn serve<I, IO, IE>(self, incoming: I, shutdown: CancellationToken) -> Result<()>
+    where
+        I: Stream<Item = Result<IO, IE>>,
+        IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
+        IO::ConnectInfo: Clone + Send + Sync + 'static,
+        IE: Into<StdError>,
+    {
+        let make_service = make_service_fn(move |connection: &IO| {
+            let info = connection.connect_info();
+            let gateway = self.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |mut request: Request<Body>| {
+                    request.extensions_mut().insert(info.clone());
+                    let gateway = gateway.clone();
+                    async move { Ok::<_, Infallible>(gateway.handle(request).await) }
+                }))
+            }
+        });
+        Server::builder(accept::from_stream(incoming))
+            .serve(make_service)
+            .with_graceful_shutdown(shutdown.cancelled_owned())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn handle(self, request: Request<Body>) -> Response<Body> {
+        let path = request.uri().path();
+        if request.method() == Method::GET && path == OPENAPI_PATH {
+            return json_response(StatusCode::OK, self.openapi.to_string());
+        }
+
+        let found = self
+            .routes
+            .iter()
+            .filter(|route| route.method == request.method())
+            .find_map(|route| {
+                route
+                    .template
+                    .matches(path)
+                    .map(|variables| (route, variables))
+            });
+        let Some((route, variables)) = found else {
+            let error =
+                DomainError::NotFound(format!("no route matches [{} {}]", request.method(), path));
+            return error_response(&self.status_message, &handle_error(error));
+        };
+
+        let grpc = self.grpc.clone();
+        route
+            .call(grpc, &self.status_message, &variables, request)
+            .await
+            .unwrap_or_else(|status| error_response(&self.status_message, &status))
+    }
+}
+
+/// OpenAPI 3 document of the gateway of `descriptor_set`, which is served on [`OPENAPI_PATH`].
+pub fn openapi(descriptor_set: &[u8]) -> Result<String> {
+    let (routes, status_message) = routes(descriptor_set)?;
+
+    Ok(serde_json::to_string_pretty(&openapi::document(
+        &routes,
+        &status_message,
+    ))?)
+}
+
+/// Reads the routes of `descriptor_set`, and the `google.rpc.Status` message errors are sent as.
+fn routes(descriptor_set: &[u8]) -> Result<(Vec<Route>, MessageDescriptor)> {
+    let pool = DescriptorPool::decode(descriptor_set).context("invalid descriptor set")?;
+    let http = pool
+        .get_extension_by_name("google.api.http")
+        .ok_or_else(|| anyhow!("the descriptor set does not define google.api.http"))?;
+    let status_message = pool
+        .get_message_by_name("google.rpc.Status")
+        .ok_or_else(|| anyhow!("the descriptor set does not define google.rpc.Status"))?;
+
+    let mut routes = Vec::new();
+    for service in pool.services() {
+        for method in service.methods() {
+            let options = method.options();
+            if !options.has_extension(&http) {
+                continue;
+            }
+            if method.is_client_streaming() || method.is_server_streaming() {
+                bail!(
+                    "[{}] streams and cannot be bound to HTTP",
+                    method.full_name()
+                );
+            }
+            let rule = options.get_extension(&http);
+            let rule = rule
+                .as_message()
+                .ok_or_else(|| anyhow!("invalid google.api.http of [{}]", method.full_name()))?;
+            let route = Route::new(rule, method.clone())
+                .with_context(|| format!("invalid google.api.http of [{}]", method.full_name()))?;
+            routes.push(route);
+        }
+    }
+
+    Ok((routes, status_message))
+}
+
+impl Route {
+    fn new(rule: &DynamicMessage, rpc: MethodDescriptor) -> Result<Self> {
+        let string = |name: &str| {
+            rule.get_field_by_name(name)
+                .and_then(|value| value.as_str().map(str::to_string))
+                .filter(|value| !value.is_empty())
+        };
+        let (method, template) = [
+            (Method::GET, "get"),
+            (Method::PUT, "put"),
+            (Method::POST, "post"),
+            (Method::DELETE, "delete"),
+            (Method::PATCH, "patch"),
+        ]
+        .into_iter()
+        .find_map(|(method, name)| string(name).map(|template| (method, template)))
+        .ok_or_else(|| anyhow!("only get, put, post, delete and patch are supported"))?;
+        let template = PathTemplate::parse(&template)?;
+        let input = rpc.input();
+        for variable in template.variables() {
+            if input.get_field_by_name(variable).is_none() {
+                bail!("[{}] has no [{}] field", input.full_name(), variable);
+            }
+        }
+        let body = string("body");
+        if let Some(field) = body.as_deref().filter(|body| *body != "*") {
+            if input.get_field_by_name(field).is_none() {
+                bail!("[{}] has no [{}] field", input.full_name(), field);
+            }
+        }
+
+        Ok(Self {
+            method,
+            template,
+            grpc_path: format!("/{}/{}", rpc.parent_service().full_name(), rpc.name()),
+            body,
+            rpc,
+            input,
+        })
+    }
+
+    /// Transcodes `request` to a call of the RPC, and its response to JSON. Errors of the RPC are
+    /// returned as responses, with the request id of the RPC.
+    async fn call(
+        &self,
+        grpc: GrpcService,
+        status_message: &MessageDescriptor,
+        variables: &[(String, String)],
+        request: Request<Body>,
+    ) -> Result<Response<Body>, Status> {
+        let (parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(Limited::new(body, MAX_BODY_SIZE))
+            .await
+            .map_err(|e| {
+                handle_error(DomainError::InvalidArgument(format!(
+                    "failed to read the request body: {}",
+                    e
+                )))
+            })?;
+        let message = self
+            .request(variables, parts.uri.query().unwrap_or_default(), &body)
+            .map_err(handle_error)?;
+
+        let mut grpc_request = Request::builder()
+            .method(Method::POST)
+            .uri(&self.grpc_path)
+            .version(Version::HTTP_2)
+            .header(CONTENT_TYPE, "application/grpc")
+            .header(TE, "trailers")
+            .body(Body::from(frame(message.encode_to_vec())))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        for name in FORWARDED_HEADERS {
+            for value in parts.headers.get_all(name) {
+                grpc_request.headers_mut().append(name, value.clone());
+            }
+        }
+        if let Some(info) = parts.extensions.get::<TlsConnectInfo>() {
+            grpc_request.extensions_mut().insert(info.clone());
+        }
+        if let Some(info) = parts.extensions.get::<TcpConnectInfo>() {
+            grpc_request.extensions_mut().insert(info.clone());
+        }
+
+        let (parts, mut body) = grpc
+            .oneshot(grpc_request)
+            .await
+            .map_err(Status::from_error)?
+            .into_parts();
+        let mut data = Vec::new();
+        while let Some(chunk) = body.data().await {
+            data.extend_from_slice(&chunk?);
+        }
+        let trailers = body.trailers().await?;
+        // errors without a message are sent in the headers
+        let status = Status::from_header_map(&parts.headers)
+            .or_else(|| trailers.as_ref().and_then(Status::from_header_map))
+            .unwrap_or_else(|| Status::internal("the response has no grpc-status"));
+
+        let mut response = if status.code() == Code::Ok {
+            let output = unframe(&data)
+                .and_then(|payload| DynamicMessage::decode(self.rpc.output(), payload).ok())
+                .ok_or_else(|| Status::internal("invalid response message"))?;
+            json_response(StatusCode::OK, to_json(&output))
+        } else {
+            error_response(status_message, &status)
+        };
+        if let Some(request_id) = parts.headers.get(request_id::HEADER) {
+            response
+                .headers_mut()
+                .insert(request_id::HEADER, request_id.clone());
+        }
+
+        Ok(response)
+    }
+
+    /// Builds the request message from the path variables, then the body or the query string.
+    fn request(
+        &self,
+        variables: &[(String, String)],
+        query: &str,
+        body: &[u8],
+    ) -> Result<DynamicMessage, DomainError> {
+        let mut fields = Map::new();
+        match self.body.as_deref() {
+            // an empty body leaves the fields unset
+            Some(_) if body.is_empty() => {}
+            Some("*") => match serde_json::from_slice(body).map_err(invalid_body)? {
+                Value::Object(object) => fields = object,
+                _ => {
+                    return Err(DomainError::InvalidArgument(
+                        "the request body must be a JSON object".to_string(),
+                    ))
+                }
+            },
+            Some(field) => {
+                fields.insert(
+                    field.to_string(),
+                    serde_json::from_slice(body).map_err(invalid_body)?,
+                );
+            }
+            None => {}
+        }
+
+        if self.body.as_deref() != Some("*") {
+            for (name, value) in form_urlencoded::parse(query.as_bytes()) {
+                let field = self
+                    .input
+                    .get_field_by_name(&name)
+                    .or_else(|| self.input.get_field_by_json_name(&name))
+                    .ok_or_else(|| {
+                        DomainError::InvalidArgument(format!("unknown query parameter [{}]", name))
+                    })?;
+                let value = parameter(&field, &value)?;
+                if field.is_list() {
+                    let values = fields
+                        .entry(field.name().to_string())
+                        .or_insert_with(|| Value::Array(Vec::new()));
+                    if let Value::Array(values) = values {
+                        values.push(value);
+                    }
+                } else {
+                    fields.insert(field.name().to_string(), value);
+                }
+            }
+        }
+
+        for (name, value) in variables {
+            let Some(field) = self.input.get_field_by_name(name) else {
+                continue;
+            };
+            // a field may be given both as `field_name` and `fieldName` in the body
+            fields.remove(field.json_name());
+            fields.insert(field.name().to_string(), parameter(&field, value)?);
+        }
+
+        DynamicMessage::deserialize_with_options(
+            self.input.clone(),
+            Value::Object(fields),
+            &DeserializeOptions::new(),
+        )
+        .map_err(|e| DomainError::InvalidArgument(format!("invalid request: {}", e)))
+    }
+}
+
+impl PathTemplate {
+    fn parse(template: &str) -> Result<Self> {
+        let Some(path) = template.strip_prefix('/') else {
+            bail!("[{}] does not start with /", template);
+        };
+        let (path, verb) = match path.rsplit_once(':') {
+            Some((path, verb)) if !verb.contains('/') && !verb.contains('}') => {
+                (path, Some(verb.to_string()))
+            }
+            _ => (path, None),
+        };
+        let segments = path
+            .split('/')
+            .map(|segment| {
+                match segment
+                    .strip_prefix('{')
+                    .and_then(|variable| variable.strip_suffix('}'))
+                {
+                    Some(variable) => {
+                        let variable = variable.strip_suffix("=*").unwrap_or(variable);
+                        if variable.contains(['=', '.', '*']) {
+                            bail!(
+                                "only single segment variables are supported: [{}]",
+                                template
+                            );
+                        }
+                        Ok(Segment::Variable(variable.to_string()))
+                    }
+                    None if segment.is_empty() || segment.contains(['{', '}', '*']) => {
+                        bail!("invalid segment [{}] in [{}]", segment, template)
+                    }
+                    None => Ok(Segment::Literal(segment.to_string())),
+                }
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { segments, verb })
+    }
+
+    /// Returns the values of the variables when `path` matches.
+    fn matches(&self, path: &str) -> Option<Vec<(String, String)>> {
+        let mut path = path.strip_prefix('/')?;
+        if let Some(verb) = &self.verb {
+            path = path.strip_suffix(verb.as_str())?.strip_suffix(':')?;
+        }
+        let segments: Vec<_> = path.split('/').collect();
+        if segments.len() != self.segments.len() {
+            return None;
+        }
+
+        let mut variables = Vec::new();
+        for (segment, value) in self.segments.iter().zip(segments) {
+            match segment {
+                Segment::Literal(literal) if literal == value => {}
+                Segment::Variable(name) if !value.is_empty() => {
+                    let value = percent_decode_str(value).decode_utf8().ok()?;
+                    variables.push((name.clone(), value.into_owned()));
+                }
+                _ => return None,
+            }
+        }
+
+        Some(variables)
+    }
+
+    fn variables(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Variable(name) => Some(name.as_str()),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    /// The template as an OpenAPI path, e.g. `/v1/jobs/{id}:cancel`.
+    fn to_openapi(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => path.push_str(&format!("/{}", literal)),
+                Segment::Variable(name) => path.push_str(&format!("/{{{}}}", name)),
+            }
+        }
+        if let Some(verb) = &self.verb {
+            path.push_str(&format!(":{}", verb));
+        }
+
+        path
+    }
+}
+
+/// The `google.rpc.Status` of `status` as JSON, with its details when it has some.
+fn error_response(status_message: &MessageDescriptor, status: &Status) -> Response<Body> {
+    let body = Some(status.details())
+        .filter(|details| !details.is_empty())
+        .and_then(|details| DynamicMessage::decode(status_message.clone(), details).ok())
+        .map(|message| to_json(&message))
+        .unwrap_or_else(|| {
+            json!({"code": status.code() as i32, "message": status.message()}).to_string()
+        });
+
+    json_response(http_status(status.code()), body)
+}
+
+/// Converts a path variable or a query parameter to the JSON value of `field`.
+fn parameter(field: &FieldDescriptor, value: &str) -> Result<Value, DomainError> {
+    match field.kind() {
+        _ if field.is_map() => Err(DomainError::InvalidArgument(format!(
+            "[{}] cannot be set in the path or the query string",
+            field.name()
+        ))),
+        Kind::Bool => value.parse().map(Value::Bool).map_err(|_| {
+            DomainError::InvalidArgument(format!("[{}] must be true or false", field.name()))
+        }),
+        Kind::Message(message) if !STRING_MESSAGES.contains(&message.full_name()) => {
+            Err(DomainError::InvalidArgument(format!(
+                "[{}] cannot be set in the path or the query string",
+                field.name()
+            )))
+        }
+        // numbers, enums and the messages above are parsed from strings
+        _ => Ok(Value::String(value.to_string())),
+    }
+}
+
+fn invalid_body(e: serde_json::Error) -> DomainError {
+    DomainError::InvalidArgument(format!("invalid JSON body: {}", e))
+}
+
+/// Prefixes `message` with the header of a gRPC message: no compression, then its length.
+fn frame(message: Vec<u8>) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(message.len() + 5);
+    frame.push(0);
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend(message);
+
+    frame
+}
+
+/// Returns the single uncompressed message of a response body.
+fn unframe(data: &[u8]) -> Option<&[u8]> {
+    let (&compressed, rest) = data.split_first()?;
+    let length = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    (compressed == 0).then(|| rest.get(4..4 + length)).flatten()
+}
+
+// every field is written, so that clients see the same shape whatever the values
+fn to_json(message: &DynamicMessage) -> String {
+    let mut json = Vec::new();
+    message
+        .serialize_with_options(
+            &mut serde_json::Serializer::new(&mut json),
+            &SerializeOptions::new().skip_default_fields(false),
+        )
+        .expect("messages are always serializable to JSON");
+
+    String::from_utf8(json).expect("serde_json writes UTF-8")
+}
+
+fn json_response(status: StatusCode, body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    response
+}
+
+/// HTTP status of a gRPC code, as in `google.rpc.Code`.
+fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::Cancelled => StatusCode::from_u16(499).expect("499 is a valid status"),
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::prost_types::field_descriptor_proto::{Label, Type};
+    use prost_reflect::prost_types::{
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+        ServiceDescriptorProto,
+    };
+
+    fn field(name: &str, json_name: &str, number: i32, kind: Type) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            json_name: Some(json_name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(kind as i32),
+            ..Default::default()
+        }
+    }
+
+    /// `test.Test/Call`, whose request has a string, a list, a number, a bool and a message.
+    fn rpc() -> MethodDescriptor {
+        let inner = DescriptorProto {
+            name: Some("Inner".to_string()),
+            field: vec![field("name", "name", 1, Type::String)],
+            ..Default::default()
+        };
+        let request = DescriptorProto {
+            name: Some("Request".to_string()),
+            field: vec![
+                field("id", "id", 1, Type::String),
+                FieldDescriptorProto {
+                    label: Some(Label::Repeated as i32),
+                    ..field("tags", "tags", 2, Type::String)
+                },
+                field("page_size", "pageSize", 3, Type::Int32),
+                field("enabled", "enabled", 4, Type::Bool),
+                FieldDescriptorProto {
+                    type_name: Some(".test.Inner".to_string()),
+                    ..field("inner", "inner", 5, Type::Message)
+                },
+            ],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("test.proto".to_string()),
+            package: Some("test".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![inner, request],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Test".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("Call".to_string()),
+                    input_type: Some(".test.Request".to_string()),
+                    output_type: Some(".test.Request".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut pool = DescriptorPool::new();
+        pool.add_file_descriptor_proto(file).unwrap();
+
+        pool.get_service_by_name("test.Test")
+            .and_then(|service| service.methods().next())
+            .unwrap()
+    }
+
+    fn route(template: &str, body: Option<&str>) -> Route {
+        let rpc = rpc();
+        Route {
+            method: Method::POST,
+            template: PathTemplate::parse(template).unwrap(),
+            grpc_path: "/test.Test/Call".to_string(),
+            body: body.map(str::to_string),
+            input: rpc.input(),
+            rpc,
+        }
+    }
+
+    fn variables(variables: &[(&str, &str)]) -> Vec<(String, String)> {
+        variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn string(message: &DynamicMessage, name: &str) -> String {
+        message
+            .get_field_by_name(name)
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    fn tags(message: &DynamicMessage) -> Vec<String> {
+        message
+            .get_field_by_name("tags")
+            .unwrap()
+            .as_list()
+            .unwrap()
+            .iter()
+            .map(|tag| tag.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn templates_are_parsed_with_their_verb() {
+        let cases = [
+            ("/v1/objects", "/v1/objects"),
+            ("/v1/objects/{id}", "/v1/objects/{id}"),
+            ("/v1/objects/{id=*}", "/v1/objects/{id}"),
+            ("/v1/jobs/{id}:cancel", "/v1/jobs/{id}:cancel"),
+            (
+                "/v1/sources/{source}/objects/{external_id}",
+                "/v1/sources/{source}/objects/{external_id}",
+            ),
+        ];
+        for (template, openapi) in cases {
+            assert_eq!(
+                PathTemplate::parse(template).unwrap().to_openapi(),
+                openapi,
+                "{}",
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        for template in [
+            "v1/objects",
+            "/v1//objects",
+            "/v1/objects/",
+            "/v1/objects/{name=objects/*}",
+            "/v1/objects/{id=**}",
+            "/v1/objects/{object.id}",
+            "/v1/objects/*",
+            "/v1/objects/id}",
+        ] {
+            assert!(PathTemplate::parse(template).is_err(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn paths_match_templates_and_decode_their_variables() {
+        let cases = [
+            (
+                "/v1/jobs/{id}:cancel",
+                "/v1/jobs/1:cancel",
+                Some(vec![("id", "1")]),
+            ),
+            (
+                "/v1/jobs/{id}:cancel",
+                "/v1/jobs/a%2Fb%20c:cancel",
+                Some(vec![("id", "a/b c")]),
+            ),
+            ("/v1/jobs/{id}:cancel", "/v1/jobs/1", None),
+            ("/v1/jobs/{id}:cancel", "/v1/jobs/1:retry", None),
+            ("/v1/jobs/{id}:cancel", "/v1/jobs/:cancel", None),
+            ("/v1/jobs/{id}:cancel", "/v1/jobs/1/2:cancel", None),
+            // a colon is part of the variable when the template has no verb
+            (
+                "/v1/jobs/{id}",
+                "/v1/jobs/1:cancel",
+                Some(vec![("id", "1:cancel")]),
+            ),
+            ("/v1/jobs/{id}", "/v1/jobs/", None),
+            ("/v1/jobs/{id}", "/v1/jobs/%FF", None),
+            ("/v1/jobs", "/v1/jobs", Some(vec![])),
+            ("/v1/jobs", "/v1/jobs/", None),
+            ("/v1/jobs", "/v1/Jobs", None),
+            (
+                "/v1/sources/{source}/objects/{external_id}",
+                "/v1/sources/crm/objects/42",
+                Some(vec![("source", "crm"), ("external_id", "42")]),
+            ),
+        ];
+        for (template, path, expected) in cases {
+            let matched = PathTemplate::parse(template).unwrap().matches(path);
+
+            assert_eq!(
+                matched,
+                expected.map(|expected| variables(&expected)),
+                "{} {}",
+                template,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn whole_bodies_fill_the_request_and_ignore_the_query() {
+        let route = route("/v1/calls/{id}", Some("*"));
+
+        let message = route
+            .request(
+                &variables(&[("id", "path")]),
+                "tags=query",
+                br#"{"id": "body", "tags": ["body"], "inner": {"name": "inner"}}"#,
+            )
+            .unwrap();
+
+        assert_eq!(string(&message, "id"), "path");
+        assert_eq!(tags(&message), vec!["body"]);
+        let inner = message.get_field_by_name("inner").unwrap();
+        assert_eq!(string(inner.as_message().unwrap(), "name"), "inner");
+    }
+
+    #[test]
+    fn path_variables_override_fields_of_the_body_by_either_name() {
+        let route = route("/v1/calls/{page_size}", Some("*"));
+
+        for body in [r#"{"pageSize": 1}"#, r#"{"page_size": 1}"#] {
+            let message = route
+                .request(&variables(&[("page_size", "5")]), "", body.as_bytes())
+                .unwrap();
+
+            assert_eq!(
+                message.get_field_by_name("page_size").unwrap().as_i32(),
+                Some(5),
+                "{}",
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn body_fields_are_read_with_the_query() {
+        let route = route("/v1/calls/{id}", Some("inner"));
+
+        let message = route
+            .request(
+                &variables(&[("id", "path")]),
+                "tags=a&tags=b&pageSize=3&enabled=true",
+                br#"{"name": "inner"}"#,
+            )
+            .unwrap();
+
+        assert_eq!(string(&message, "id"), "path");
+        assert_eq!(tags(&message), vec!["a", "b"]);
+        assert_eq!(
+            message.get_field_by_name("page_size").unwrap().as_i32(),
+            Some(3)
+        );
+        assert_eq!(
+            message.get_field_by_name("enabled").unwrap().as_bool(),
+            Some(true)
+        );
+        let inner = message.get_field_by_name("inner").unwrap();
+        assert_eq!(string(inner.as_message().unwrap(), "name"), "inner");
+    }
+
+    #[test]
+    fn empty_bodies_leave_the_fields_unset() {
+        for body in [Some("*"), Some("inner")] {
+            let message = route("/v1/calls", body).request(&[], "", b"").unwrap();
+
+            assert!(!message.has_field_by_name("inner"), "{:?}", body);
+        }
+    }
+
+    #[test]
+    fn invalid_requests_are_rejected() {
+        let cases = [
+            (Some("*"), "", "[1]"),
+            (Some("*"), "", "{"),
+            (Some("*"), "", r#"{"unknown": 1}"#),
+            (Some("inner"), "", r#"{"unknown": 1}"#),
+            (None, "unknown=1", ""),
+            (None, "enabled=yes", ""),
+            (None, "page_size=many", ""),
+            (None, "inner=name", ""),
+        ];
+        for (body, query, content) in cases {
+            let result = route("/v1/calls", body).request(&[], query, content.as_bytes());
+
+            assert!(
+                matches!(result, Err(DomainError::InvalidArgument(_))),
+                "{:?} {} {}",
+                body,
+                query,
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn codes_map_to_http_statuses() {
+        let cases = [
+            (Code::Ok, 200),
+            (Code::Cancelled, 499),
+            (Code::Unknown, 500),
+            (Code::InvalidArgument, 400),
+            (Code::DeadlineExceeded, 504),
+            (Code::NotFound, 404),
+            (Code::AlreadyExists, 409),
+            (Code::PermissionDenied, 403),
+            (Code::ResourceExhausted, 429),
+            (Code::FailedPrecondition, 400),
+            (Code::Aborted, 409),
+            (Code::OutOfRange, 400),
+            (Code::Unimplemented, 501),
+            (Code::Internal, 500),
+            (Code::Unavailable, 503),
+            (Code::DataLoss, 500),
+            (Code::Unauthenticated, 401),
+        ];
+        for (code, status) in cases {
+            assert_eq!(http_status(code).as_u16(), status, "{:?}", code);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use super::{Route, STRING_MESSAGES};
+use crate::auth::api_key;
+use crate::tenants;
+use prost_reflect::{FieldDescriptor, FileDescriptor, Kind, MessageDescriptor};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, VecDeque};
+
+/// OpenAPI 3 document of `routes`, with a schema for each message they read or write.
+pub(super) fn document(routes: &[Route], status_message: &MessageDescriptor) -> Value {
+    let mut schemas = Schemas::default();
+    let error = schemas.reference(status_message);
+
+    let mut paths = BTreeMap::<String, Map<String, Value>>::new();
+    for route in routes {
+        let operation = operation(route, &error, &mut schemas);
+        paths
+            .entry(route.template.to_openapi())
+            .or_default()
+            .insert(route.method.as_str().to_lowercase(), operation);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "poo-pad-pong",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas.resolve(),
+            "securitySchemes": {
+                "ApiKey": {"type": "apiKey", "in": "header", "name": api_key::HEADER},
+                "BearerAuth": {"type": "http", "scheme": "bearer", "bearerFormat": "JWT"},
+            },
+        },
+        "security": [{"ApiKey": []}, {"BearerAuth": []}],
+    })
+}
+
+fn operation(route: &Route, error: &Value, schemas: &mut Schemas) -> Value {
+    let variables: Vec<_> = route.template.variables().collect();
+    let mut parameters = Vec::new();
+    for variable in &variables {
+        if let Some(field) = route.input.get_field_by_name(variable) {
+            parameters.push(json!({
+                "name": variable,
+                "in": "path",
+                "required": true,
+                "schema": schemas.field(&field),
+            }));
+        }
+    }
+    if route.body.is_none() {
+        for field in route.input.fields() {
+            if variables.contains(&field.name()) || !is_parameter(&field) {
+                continue;
+            }
+            let mut parameter = json!({
+                "name": field.json_name(),
+                "in": "query",
+                "schema": schemas.field(&field),
+            });
+            if let Some(description) = comments(&field.parent_file(), field.path()) {
+                parameter["description"] = description.into();
+            }
+            if field.is_list() {
+                parameter["explode"] = true.into();
+            }
+            parameters.push(parameter);
+        }
+    }
+    parameters.push(json!({
+        "name": tenants::HEADER,
+        "in": "header",
+        "description": "Tenant of the request, the tenant of the credentials by default.",
+        "schema": {"type": "string"},
+    }));
+
+    let mut operation = json!({
+        "operationId": format!("{}_{}", route.rpc.parent_service().name(), route.rpc.name()),
+        "tags": [route.rpc.parent_service().name()],
+        "parameters": parameters,
+        "responses": {
+            "200": {
+                "description": "OK",
+                "content": {"application/json": {"schema": schemas.reference(&route.rpc.output())}},
+            },
+            "default": {
+                "description": "The error of the RPC, with its details.",
+                "content": {"application/json": {"schema": error}},
+            },
+        },
+    });
+    if let Some(description) = comments(&route.rpc.parent_file(), route.rpc.path()) {
+        operation["description"] = description.into();
+    }
+    let body = match route.body.as_deref() {
+        // path variables are read from the path even when they are in the body
+        Some("*") if variables.is_empty() => Some(schemas.reference(&route.input)),
+        Some("*") => Some(schemas.message(&route.input, &variables)),
+        Some(field) => route
+            .input
+            .get_field_by_name(field)
+            .map(|field| schemas.field(&field)),
+        None => None,
+    };
+    if let Some(schema) = body {
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": {"application/json": {"schema": schema}},
+        });
+    }
+
+    operation
+}
+
+/// Whether `field` can be set in the query string, see `parameter` of the gateway.
+fn is_parameter(field: &FieldDescriptor) -> bool {
+    match field.kind() {
+        _ if field.is_map() => false,
+        Kind::Message(message) => STRING_MESSAGES.contains(&message.full_name()),
+        _ => true,
+    }
+}
+
+/// Schemas of `components.schemas`, keyed by the full name of their message.
+#[derive(Default)]
+struct Schemas {
+    resolved: BTreeMap<String, Value>,
+    pending: VecDeque<MessageDescriptor>,
+}
+
+impl Schemas {
+    /// Refers to the schema of `message`, which is added to the components.
+    fn reference(&mut self, message: &MessageDescriptor) -> Value {
+        self.pending.push_back(message.clone());
+        json!({ "$ref": format!("#/components/schemas/{}", message.full_name()) })
+    }
+
+    /// Schema of `message` without the fields in `excluded`.
+    fn message(&mut self, message: &MessageDescriptor, excluded: &[&str]) -> Value {
+        let mut properties = Map::new();
+        for field in message.fields() {
+            if excluded.contains(&field.name()) {
+                continue;
+            }
+            let mut schema = self.field(&field);
+            if let Some(description) = comments(&field.parent_file(), field.path()) {
+                // siblings of $ref are ignored, so the reference is wrapped
+                schema = match schema {
+                    Value::Object(object) if object.contains_key("$ref") => {
+                        json!({"allOf": [object], "description": description})
+                    }
+                    mut schema => {
+                        schema["description"] = description.into();
+                        schema
+                    }
+                };
+            }
+            properties.insert(field.json_name().to_string(), schema);
+        }
+
+        let mut schema = json!({"type": "object", "properties": properties});
+        if let Some(description) = comments(&message.parent_file(), message.path()) {
+            schema["description"] = description.into();
+        }
+
+        schema
+    }
+
+    fn field(&mut self, field: &FieldDescriptor) -> Value {
+        match field.kind() {
+            Kind::Message(entry) if field.is_map() => {
+                let value = entry.map_entry_value_field();
+                json!({"type": "object", "additionalProperties": self.kind(&value.kind())})
+            }
+            kind if field.is_list() => json!({"type": "array", "items": self.kind(&kind)}),
+            kind => self.kind(&kind),
+        }
+    }
+
+    /// Schema of the proto3 JSON mapping of `kind`.
+    fn kind(&mut self, kind: &Kind) -> Value {
+        match kind {
+            Kind::Double => json!({"type": "number", "format": "double"}),
+            Kind::Float => json!({"type": "number", "format": "float"}),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                json!({"type": "integer", "format": "int32"})
+            }
+            Kind::Uint32 | Kind::Fixed32 => json!({"type": "integer", "format": "int64"}),
+            // 64 bit integers are strings, JavaScript numbers cannot hold them
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                json!({"type": "string", "format": "int64"})
+            }
+            Kind::Uint64 | Kind::Fixed64 => json!({"type": "string", "format": "uint64"}),
+            Kind::Bool => json!({"type": "boolean"}),
+            Kind::String => json!({"type": "string"}),
+            Kind::Bytes => json!({"type": "string", "format": "byte"}),
+            Kind::Enum(descriptor) => json!({
+                "type": "string",
+                "enum": descriptor.values().map(|value| value.name().to_string()).collect::<Vec<_>>(),
+            }),
+            Kind::Message(message) => match message.full_name() {
+                "google.protobuf.Timestamp" => json!({"type": "string", "format": "date-time"}),
+                "google.protobuf.Duration" => json!({"type": "string", "example": "1.5s"}),
+                "google.protobuf.FieldMask" => {
+                    json!({"type": "string", "example": "attributes,source"})
+                }
+                "google.protobuf.StringValue" => json!({"type": "string", "nullable": true}),
+                "google.protobuf.Empty" => json!({"type": "object"}),
+                "google.protobuf.Any" => json!({
+                    "type": "object",
+                    "properties": {"@type": {"type": "string"}},
+                    "additionalProperties": true,
+                }),
+                _ => self.reference(message),
+            },
+        }
+    }
+
+    fn resolve(mut self) -> BTreeMap<String, Value> {
+        while let Some(message) = self.pending.pop_front() {
+            if !self.resolved.contains_key(message.full_name()) {
+                let schema = self.message(&message, &[]);
+                self.resolved
+                    .insert(message.full_name().to_string(), schema);
+            }
+        }
+
+        self.resolved
+    }
+}
+
+/// Leading comments of the element at `path` of `file`.
+fn comments(file: &FileDescriptor, path: &[i32]) -> Option<String> {
+    let comments = file
+        .file_descriptor_proto()
+        .source_code_info
+        .as_ref()?
+        .location
+        .iter()
+        .find(|location| location.path == path)?
+        .leading_comments
+        .as_deref()?
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let comments = comments.trim();
+
+    (!comments.is_empty()).then(|| comments.to_string())
+}
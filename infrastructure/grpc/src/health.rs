@@ -0,0 +1,93 @@
+use domain::health::HealthRepository;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tonic::server::NamedService;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+/// How often the dependencies are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Reports the database, whose checks include pending migrations.
+pub const DATABASE_SERVICE: &str = "database";
+/// Reports whether messages are being received.
+pub const SUBSCRIPTION_SERVICE: &str = "subscription";
+
+/// Name `service` is registered as in `grpc.health.v1.Health`.
+pub fn service_name<S: NamedService>(_service: &S) -> &'static str {
+    S::NAME
+}
+
+/// Keeps the statuses of `grpc.health.v1.Health` in line with the dependencies.
+#[derive(Clone)]
+pub struct HealthMonitor<T: HealthRepository> {
+    repository: T,
+    reporter: HealthReporter,
+}
+
+impl<T: HealthRepository> HealthMonitor<T> {
+    pub fn new(repository: T, reporter: HealthReporter) -> Self {
+        Self {
+            repository,
+            reporter,
+        }
+    }
+
+    /// Checks the dependencies until `shutdown` is cancelled, then reports NOT_SERVING for all.
+    /// `services` and the server as a whole are SERVING while every dependency is healthy. The
+    /// database and, when `subscribed` is given, the subscription are also reported on their own.
+    pub async fn run(
+        &self,
+        services: &[&str],
+        subscribed: Option<watch::Receiver<bool>>,
+        shutdown: &CancellationToken,
+    ) {
+        let mut reporter = self.reporter.clone();
+        let mut names = vec!["", DATABASE_SERVICE];
+        names.extend_from_slice(services);
+        if subscribed.is_some() {
+            names.push(SUBSCRIPTION_SERVICE);
+        }
+
+        while !shutdown.is_cancelled() {
+            let database = match self.repository.check().await {
+                Ok(()) => ServingStatus::Serving,
+                Err(e) => {
+                    tracing::warn!(error = %e, "database is unhealthy");
+                    ServingStatus::NotServing
+                }
+            };
+            let subscription = match &subscribed {
+                Some(subscribed) if !*subscribed.borrow() => ServingStatus::NotServing,
+                _ => ServingStatus::Serving,
+            };
+            let overall =
+                if database == ServingStatus::Serving && subscription == ServingStatus::Serving {
+                    ServingStatus::Serving
+                } else {
+                    ServingStatus::NotServing
+                };
+
+            for name in &names {
+                let status = match *name {
+                    DATABASE_SERVICE => database,
+                    SUBSCRIPTION_SERVICE => subscription,
+                    _ => overall,
+                };
+                reporter.set_service_status(name, status).await;
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+                _ = shutdown.cancelled() => {}
+            }
+        }
+
+        // stop receiving traffic while draining
+        for name in &names {
+            reporter
+                .set_service_status(name, ServingStatus::NotServing)
+                .await;
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::api::jobs_server::Jobs;
+use crate::api::{
+    CancelJobRequest, CancelJobResponse, GetJobRequest, GetJobResponse, Job, JobStatus, JobType,
+    ListJobsRequest, ListJobsResponse,
+};
+use crate::auth::authorize;
+use crate::error::handle_error;
+use crate::tenants::tenant;
+use crate::timestamp::to_timestamp;
+use domain::auth::Permission;
+use domain::error::DomainError;
+use domain::jobs::{
+    Job as JobModel, JobRepository, JobStatus as JobStatusModel, JobType as JobTypeModel,
+};
+use tonic::{Request, Response, Status};
+
+const DEFAULT_PAGE_SIZE: u64 = 100;
+const MAX_PAGE_SIZE: u64 = 1_000;
+
+#[derive(Debug)]
+pub struct JobsServerImpl<T: JobRepository> {
+    repository: T,
+}
+
+impl<T: JobRepository> JobsServerImpl<T> {
+    pub fn new(repository: T) -> Self {
+        Self { repository }
+    }
+}
+
+#[tonic::async_trait]
+impl<T: JobRepository> Jobs for JobsServerImpl<T> {
+    async fn get_job(
+        &self,
+        request: Request<GetJobRequest>,
+    ) -> Result<Response<GetJobResponse>, Status> {
+        authorize(&request, Permission::JobsRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository
+            .get(request.id.clone())
+            .await
+            .map_err(handle_error)?
+            .ok_or_else(|| {
+                handle_error(DomainError::NotFound(format!(
+                    "job [{}] not found",
+                    request.id
+                )))
+            })?;
+
+        let response = GetJobResponse {
+            job: Some(result.into()),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn list_jobs(
+        &self,
+        request: Request<ListJobsRequest>,
+    ) -> Result<Response<ListJobsResponse>, Status> {
+        authorize(&request, Permission::JobsRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let page_size = match request.page_size as u64 {
+            0 => DEFAULT_PAGE_SIZE,
+            v => v.min(MAX_PAGE_SIZE),
+        };
+        let rule_id = match request.rule_id.is_empty() {
+            true => None,
+            false => Some(request.rule_id),
+        };
+        let status = match JobStatus::from_i32(request.status) {
+            Some(JobStatus::Unspecified) => None,
+            Some(v) => Some(v.into()),
+            None => {
+                return Err(handle_error(DomainError::InvalidArgument(format!(
+                    "unknown status [{}]",
+                    request.status
+                ))))
+            }
+        };
+        // the page token is the id of the last job of the previous page
+        let after = match request.page_token.is_empty() {
+            true => None,
+            false => Some(request.page_token),
+        };
+
+        let result = repository
+            .find_all(rule_id, status, after, page_size)
+            .await
+            .map_err(handle_error)?;
+
+        let next_page_token = match result.len() as u64 == page_size {
+            true => result.last().map(|job| job.id.clone()).unwrap_or_default(),
+            false => "".to_string(),
+        };
+        let response = ListJobsResponse {
+            jobs: result.into_iter().map(Into::into).collect::<Vec<Job>>(),
+            next_page_token,
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn cancel_job(
+        &self,
+        request: Request<CancelJobRequest>,
+    ) -> Result<Response<CancelJobResponse>, Status> {
+        authorize(&request, Permission::JobsWrite).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository.cancel(request.id).await.map_err(handle_error)?;
+
+        let response = CancelJobResponse {
+            job: Some(result.into()),
+        };
+
+        Ok(Response::new(response))
+    }
+}
+
+impl From<JobModel> for Job {
+    fn from(value: JobModel) -> Self {
+        Self {
+            id: value.id,
+            r#type: JobType::from(value.job_type) as i32,
+            rule_id: value.rule_id,
+            status: JobStatus::from(value.status) as i32,
+            processed_objects: value.processed_objects,
+            skipped_objects: value.skipped_objects,
+            total_objects: value.total_objects.unwrap_or_default(),
+            error: value.error.unwrap_or_default(),
+            created_at: Some(to_timestamp(value.created_at)),
+            started_at: value.started_at.map(to_timestamp),
+            finished_at: value.finished_at.map(to_timestamp),
+        }
+    }
+}
+
+impl From<JobTypeModel> for JobType {
+    fn from(value: JobTypeModel) -> Self {
+        match value {
+            JobTypeModel::CreateKeys => JobType::CreateKeys,
+            JobTypeModel::UpdateKeys => JobType::UpdateKeys,
+            JobTypeModel::DeleteKeys => JobType::DeleteKeys,
+        }
+    }
+}
+
+impl From<JobStatusModel> for JobStatus {
+    fn from(value: JobStatusModel) -> Self {
+        match value {
+            JobStatusModel::Pending => JobStatus::Pending,
+            JobStatusModel::Running => JobStatus::Running,
+            JobStatusModel::Succeeded => JobStatus::Succeeded,
+            JobStatusModel::Failed => JobStatus::Failed,
+            JobStatusModel::Cancelled => JobStatus::Cancelled,
+        }
+    }
+}
+
+impl From<JobStatus> for JobStatusModel {
+    fn from(value: JobStatus) -> Self {
+        match value {
+            JobStatus::Unspecified | JobStatus::Pending => JobStatusModel::Pending,
+            JobStatus::Running => JobStatusModel::Running,
+            JobStatus::Succeeded => JobStatusModel::Succeeded,
+            JobStatus::Failed => JobStatusModel::Failed,
+            JobStatus::Cancelled => JobStatusModel::Cancelled,
+        }
+    }
+}
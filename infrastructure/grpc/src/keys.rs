@@ -0,0 +1,167 @@
+use crate::api::keys_server::Keys;
+use crate::api::{
+    FindObjectsByKeyRequest, FindObjectsByKeyResponse, GetKeysForObjectRequest,
+    GetKeysForObjectResponse, GetKeysForRuleRequest, GetKeysForRuleResponse, Key,
+    StreamKeysRequest, StreamKeysResponse,
+};
+use crate::auth::authorize;
+use crate::error::handle_error;
+use crate::export::{batch_size, export, ExportStream};
+use crate::tenants::tenant;
+use crate::timestamp::{from_timestamp, to_timestamp};
+use domain::auth::Permission;
+use domain::error::DomainError;
+use domain::keys::{Key as KeyModel, KeyRepository};
+use tonic::{Request, Response, Status};
+
+const DEFAULT_PAGE_SIZE: u64 = 100;
+const MAX_PAGE_SIZE: u64 = 1_000;
+
+#[derive(Debug)]
+pub struct KeysServerImpl<T: KeyRepository> {
+    repository: T,
+}
+
+impl<T: KeyRepository> KeysServerImpl<T> {
+    pub fn new(repository: T) -> Self {
+        Self { repository }
+    }
+}
+
+#[tonic::async_trait]
+impl<T: KeyRepository> Keys for KeysServerImpl<T> {
+    type StreamKeysStream = ExportStream<StreamKeysResponse>;
+
+    async fn get_keys_for_object(
+        &self,
+        request: Request<GetKeysForObjectRequest>,
+    ) -> Result<Response<GetKeysForObjectResponse>, Status> {
+        authorize(&request, Permission::KeysRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository
+            .find_by_object(request.object_id)
+            .await
+            .map_err(handle_error)?;
+
+        let response = GetKeysForObjectResponse {
+            keys: result.into_iter().map(Into::into).collect::<Vec<Key>>(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn get_keys_for_rule(
+        &self,
+        request: Request<GetKeysForRuleRequest>,
+    ) -> Result<Response<GetKeysForRuleResponse>, Status> {
+        authorize(&request, Permission::KeysRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let page_size = match request.page_size as u64 {
+            0 => DEFAULT_PAGE_SIZE,
+            v => v.min(MAX_PAGE_SIZE),
+        };
+        // the page token is the object id of the last key of the previous page
+        let after = match request.page_token.is_empty() {
+            true => None,
+            false => Some(request.page_token),
+        };
+
+        let result = repository
+            .find_by_rule(request.rule_id, after, page_size)
+            .await
+            .map_err(handle_error)?;
+
+        let next_page_token = match result.len() as u64 == page_size {
+            true => result
+                .last()
+                .map(|key| key.object_id.clone())
+                .unwrap_or_default(),
+            false => "".to_string(),
+        };
+        let response = GetKeysForRuleResponse {
+            keys: result.into_iter().map(Into::into).collect::<Vec<Key>>(),
+            next_page_token,
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn find_objects_by_key(
+        &self,
+        request: Request<FindObjectsByKeyRequest>,
+    ) -> Result<Response<FindObjectsByKeyResponse>, Status> {
+        authorize(&request, Permission::KeysRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository
+            .find_objects_by_key(request.rule_id, request.key)
+            .await
+            .map_err(handle_error)?;
+
+        let response = FindObjectsByKeyResponse {
+            objects: result.into_iter().map(Into::into).collect(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn stream_keys(
+        &self,
+        request: Request<StreamKeysRequest>,
+    ) -> Result<Response<Self::StreamKeysStream>, Status> {
+        authorize(&request, Permission::KeysRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let updated_since = match request.updated_since {
+            Some(v) => Some(from_timestamp(v).ok_or_else(|| {
+                handle_error(DomainError::InvalidArgument(
+                    "updated_since is not a valid timestamp".to_string(),
+                ))
+            })?),
+            None => None,
+        };
+        let batch_size = batch_size(request.batch_size);
+
+        let stream = export(batch_size, move |after| {
+            let repository = repository.clone();
+            async move {
+                let keys = repository
+                    .find_batch(updated_since, after, batch_size)
+                    .await?;
+
+                Ok(keys
+                    .into_iter()
+                    .map(|(key, cursor)| {
+                        let response = StreamKeysResponse {
+                            key: Some(key.into()),
+                            updated_at: Some(to_timestamp(cursor.updated_at)),
+                        };
+                        (response, cursor)
+                    })
+                    .collect())
+            }
+        });
+
+        Ok(Response::new(stream))
+    }
+}
+
+impl From<KeyModel> for Key {
+    fn from(value: KeyModel) -> Self {
+        Self {
+            rule_id: value.rule_id,
+            object_id: value.object_id,
+            key: value.key,
+        }
+    }
+}
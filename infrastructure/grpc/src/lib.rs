@@ -1,8 +1,23 @@
+pub mod api_keys;
+pub mod auth;
 pub mod error;
+pub mod export;
 pub mod fields;
+pub mod gateway;
+pub mod health;
+pub mod jobs;
+pub mod keys;
+pub mod metrics;
 pub mod objects;
+pub mod role_bindings;
 pub mod rules;
+pub mod tenants;
+pub mod timestamp;
+pub mod tls;
+pub mod trace;
 
 pub mod api;
+#[path = "google.rpc.rs"]
+pub mod rpc;
 
 pub const API_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("api");
@@ -0,0 +1,119 @@
+use anyhow::{Context as _, Result};
+use prost_reflect::DescriptorPool;
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::codegen::http::{Request, Response};
+use tonic::Code;
+use tower::{Layer, Service};
+
+/// Label of the RPCs whose path is not a method of the descriptor set, so clients cannot add series.
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// Records the count, status code and latency of every RPC.
+/// Codes are read from the response headers, so a stream failing after its first message counts as OK.
+#[derive(Clone, Debug)]
+pub struct MetricsLayer {
+    methods: Arc<HashSet<String>>,
+}
+
+impl MetricsLayer {
+    /// Labels the methods of `descriptor_set` by path, and every other RPC as `unknown`.
+    pub fn new(descriptor_set: &[u8]) -> Result<Self> {
+        let pool = DescriptorPool::decode(descriptor_set).context("invalid descriptor set")?;
+        let mut methods = HashSet::new();
+        for service in pool.services() {
+            for method in service.methods() {
+                methods.insert(format!("/{}/{}", service.full_name(), method.name()));
+            }
+        }
+
+        Ok(Self {
+            methods: Arc::new(methods),
+        })
+    }
+
+    fn method_label(&self, path: &str) -> String {
+        if self.methods.contains(path) {
+            path.to_string()
+        } else {
+            UNKNOWN_METHOD.to_string()
+        }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MetricsService<S> {
+    inner: S,
+    layer: MetricsLayer,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for MetricsService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let method = self.layer.method_label(request.uri().path());
+        let started_at = Instant::now();
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = response.await;
+            let code = match &response {
+                Ok(response) => response
+                    .headers()
+                    .get("grpc-status")
+                    .map_or(Code::Ok, |code| Code::from_bytes(code.as_bytes())),
+                Err(_) => Code::Unknown,
+            };
+            ::metrics::observe_rpc(&method, &format!("{:?}", code), started_at.elapsed());
+
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::API_DESCRIPTOR_SET;
+
+    #[test]
+    fn only_methods_of_the_descriptor_set_are_labelled_by_path() {
+        let layer = MetricsLayer::new(API_DESCRIPTOR_SET).unwrap();
+
+        let cases = [
+            ("/api.Rules/GetRule", "/api.Rules/GetRule"),
+            ("/api.Rules/Unknown", UNKNOWN_METHOD),
+            ("/api.Rules/GetRule/", UNKNOWN_METHOD),
+            ("/grpc.health.v1.Health/Check", UNKNOWN_METHOD),
+            ("/", UNKNOWN_METHOD),
+        ];
+        for (path, label) in cases {
+            assert_eq!(layer.method_label(path), label, "{}", path);
+        }
+    }
+}
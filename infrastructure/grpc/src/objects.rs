@@ -1,12 +1,25 @@
 use crate::api::objects_server::Objects;
 use crate::api::{
     AddObjectRequest, AddObjectResponse, DeleteObjectRequest, DeleteObjectResponse,
-    GetObjectRequest, GetObjectResponse, GetObjectsRequest, GetObjectsResponse, Object,
-    UpdateObjectRequest, UpdateObjectResponse,
+    GetObjectRequest, GetObjectResponse, GetObjectsRequest, GetObjectsResponse, ImportObjectError,
+    ImportObjectsRequest, ImportObjectsResponse, Object, StreamObjectsRequest,
+    StreamObjectsResponse, UpdateObjectRequest, UpdateObjectResponse, UpsertObjectRequest,
+    UpsertObjectResponse, UpsertResult,
 };
+use crate::auth::authorize;
 use crate::error::handle_error;
-use domain::objects::{Object as ObjectModel, ObjectRepository};
-use tonic::{Request, Response, Status};
+use crate::export::{batch_size, export, ExportStream};
+use crate::tenants::tenant;
+use crate::timestamp::{from_timestamp, to_timestamp};
+use domain::auth::Permission;
+use domain::error::DomainError;
+use domain::objects::{Object as ObjectModel, ObjectRepository, UpsertResult as UpsertResultModel};
+use std::collections::HashMap;
+use std::mem;
+use tonic::{Request, Response, Status, Streaming};
+
+/// Number of streamed records written to the database in one transaction.
+const IMPORT_CHUNK_SIZE: usize = 1_000;
 
 #[derive(Debug)]
 pub struct ObjectsServerImpl<T: ObjectRepository> {
@@ -17,15 +30,67 @@ impl<T: ObjectRepository> ObjectsServerImpl<T> {
     pub fn new(repository: T) -> Self {
         Self { repository }
     }
+
+    async fn import_chunk(
+        repository: &T,
+        chunk: Vec<HashMap<String, String>>,
+        summary: &mut ImportObjectsResponse,
+    ) {
+        let offset = summary.received;
+        let size = chunk.len() as u64;
+
+        match repository.create_many(chunk.clone()).await {
+            Ok(results) => Self::add_results(summary, offset, results),
+            Err(_) if size > 1 => {
+                // the whole chunk has been rolled back, so its records are written one by one
+                // to report the ones that fail
+                for (index, record) in (offset..).zip(chunk) {
+                    match repository.create_many(vec![record]).await {
+                        Ok(results) => Self::add_results(summary, index, results),
+                        Err(e) => summary.errors.push(ImportObjectError {
+                            index,
+                            message: e.to_string(),
+                        }),
+                    }
+                }
+            }
+            Err(e) => summary.errors.push(ImportObjectError {
+                index: offset,
+                message: e.to_string(),
+            }),
+        }
+
+        summary.received += size;
+    }
+
+    fn add_results(
+        summary: &mut ImportObjectsResponse,
+        offset: u64,
+        results: Vec<Result<ObjectModel, DomainError>>,
+    ) {
+        for (index, result) in (offset..).zip(results) {
+            match result {
+                Ok(_) => summary.imported += 1,
+                Err(e) => summary.errors.push(ImportObjectError {
+                    index,
+                    message: e.to_string(),
+                }),
+            }
+        }
+    }
 }
 
 #[tonic::async_trait]
 impl<T: ObjectRepository> Objects for ObjectsServerImpl<T> {
     async fn get_objects(
         &self,
-        _request: Request<GetObjectsRequest>,
+        request: Request<GetObjectsRequest>,
     ) -> Result<Response<GetObjectsResponse>, Status> {
-        let result = self.repository.find_all().await.map_err(handle_error)?;
+        authorize(&request, Permission::ObjectsRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let result = repository.find_all().await.map_err(handle_error)?;
 
         let response = GetObjectsResponse {
             objects: result.into_iter().map(Into::into).collect::<Vec<Object>>(),
@@ -34,16 +99,60 @@ impl<T: ObjectRepository> Objects for ObjectsServerImpl<T> {
         Ok(Response::new(response))
     }
 
+    type StreamObjectsStream = ExportStream<StreamObjectsResponse>;
+
+    async fn stream_objects(
+        &self,
+        request: Request<StreamObjectsRequest>,
+    ) -> Result<Response<Self::StreamObjectsStream>, Status> {
+        authorize(&request, Permission::ObjectsRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let updated_since = match request.updated_since {
+            Some(v) => Some(from_timestamp(v).ok_or_else(|| {
+                handle_error(DomainError::InvalidArgument(
+                    "updated_since is not a valid timestamp".to_string(),
+                ))
+            })?),
+            None => None,
+        };
+        let batch_size = batch_size(request.batch_size);
+
+        let stream = export(batch_size, move |after| {
+            let repository = repository.clone();
+            async move {
+                let objects = repository
+                    .find_batch(updated_since, after, batch_size)
+                    .await?;
+
+                Ok(objects
+                    .into_iter()
+                    .map(|(object, cursor)| {
+                        let response = StreamObjectsResponse {
+                            object: Some(object.into()),
+                            updated_at: Some(to_timestamp(cursor.updated_at)),
+                        };
+                        (response, cursor)
+                    })
+                    .collect())
+            }
+        });
+
+        Ok(Response::new(stream))
+    }
+
     async fn get_object(
         &self,
         request: Request<GetObjectRequest>,
     ) -> Result<Response<GetObjectResponse>, Status> {
-        let request = request.into_inner();
-        let result = self
+        authorize(&request, Permission::ObjectsRead).map_err(handle_error)?;
+        let repository = self
             .repository
-            .get(request.id)
-            .await
-            .map_err(handle_error)?;
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository.get(request.id).await.map_err(handle_error)?;
 
         let response = GetObjectResponse {
             object: result.map(Into::into),
@@ -56,9 +165,12 @@ impl<T: ObjectRepository> Objects for ObjectsServerImpl<T> {
         &self,
         request: Request<AddObjectRequest>,
     ) -> Result<Response<AddObjectResponse>, Status> {
-        let request = request.into_inner();
-        let result = self
+        authorize(&request, Permission::ObjectsWrite).map_err(handle_error)?;
+        let repository = self
             .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository
             .create(request.attributes)
             .await
             .map_err(handle_error)?;
@@ -69,16 +181,101 @@ impl<T: ObjectRepository> Objects for ObjectsServerImpl<T> {
         Ok(Response::new(response))
     }
 
+    async fn import_objects(
+        &self,
+        request: Request<Streaming<ImportObjectsRequest>>,
+    ) -> Result<Response<ImportObjectsResponse>, Status> {
+        authorize(&request, Permission::ObjectsWrite).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let mut stream = request.into_inner();
+        let mut summary = ImportObjectsResponse::default();
+        let mut chunk = Vec::with_capacity(IMPORT_CHUNK_SIZE);
+
+        while let Some(record) = stream.message().await? {
+            chunk.push(record.attributes);
+            if chunk.len() == IMPORT_CHUNK_SIZE {
+                Self::import_chunk(&repository, mem::take(&mut chunk), &mut summary).await;
+            }
+        }
+        if !chunk.is_empty() {
+            Self::import_chunk(&repository, chunk, &mut summary).await;
+        }
+
+        Ok(Response::new(summary))
+    }
+
+    async fn upsert_object(
+        &self,
+        request: Request<UpsertObjectRequest>,
+    ) -> Result<Response<UpsertObjectResponse>, Status> {
+        authorize(&request, Permission::ObjectsWrite).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        if request.source.is_empty() || request.external_id.is_empty() {
+            return Err(handle_error(DomainError::InvalidArgument(
+                "source and external_id must not be empty".to_string(),
+            )));
+        }
+        let (object, result) = repository
+            .upsert(request.source, request.external_id, request.attributes)
+            .await
+            .map_err(handle_error)?;
+        let response = UpsertObjectResponse {
+            object: Some(object.into()),
+            result: UpsertResult::from(result).into(),
+        };
+
+        Ok(Response::new(response))
+    }
+
     async fn update_object(
         &self,
         request: Request<UpdateObjectRequest>,
     ) -> Result<Response<UpdateObjectResponse>, Status> {
-        let request = request.into_inner();
-        let result = self
+        authorize(&request, Permission::ObjectsWrite).map_err(handle_error)?;
+        let repository = self
             .repository
-            .update(request.id, request.attributes)
-            .await
-            .map_err(handle_error)?;
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = match request.update_mask {
+            Some(mask) if !mask.paths.iter().any(|path| path == "attributes") => {
+                let keys = mask
+                    .paths
+                    .into_iter()
+                    .map(|path| {
+                        path.strip_prefix("attributes.")
+                            .filter(|key| !key.is_empty())
+                            .map(str::to_string)
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| {
+                        handle_error(DomainError::InvalidArgument(
+                            "update_mask paths must be [attributes] or [attributes.<key>]"
+                                .to_string(),
+                        ))
+                    })?;
+
+                let mut attributes = request.attributes;
+                let mut set = HashMap::new();
+                let mut unset = Vec::new();
+                for key in keys {
+                    match attributes.remove(&key) {
+                        Some(value) => {
+                            set.insert(key, value);
+                        }
+                        None => unset.push(key),
+                    }
+                }
+
+                repository.patch(request.id, set, unset).await
+            }
+            _ => repository.update(request.id, request.attributes).await,
+        }
+        .map_err(handle_error)?;
         let response = UpdateObjectResponse {
             object: Some(result.into()),
         };
@@ -90,12 +287,12 @@ impl<T: ObjectRepository> Objects for ObjectsServerImpl<T> {
         &self,
         request: Request<DeleteObjectRequest>,
     ) -> Result<Response<DeleteObjectResponse>, Status> {
-        let request = request.into_inner();
-        let _ = self
+        authorize(&request, Permission::ObjectsWrite).map_err(handle_error)?;
+        let repository = self
             .repository
-            .delete(request.id)
-            .await
-            .map_err(handle_error)?;
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let _ = repository.delete(request.id).await.map_err(handle_error)?;
         let response = DeleteObjectResponse {};
 
         Ok(Response::new(response))
@@ -110,3 +307,165 @@ impl From<ObjectModel> for Object {
         }
     }
 }
+
+impl From<UpsertResultModel> for UpsertResult {
+    fn from(value: UpsertResultModel) -> Self {
+        match value {
+            UpsertResultModel::Created => UpsertResult::Created,
+            UpsertResultModel::Updated => UpsertResult::Updated,
+            UpsertResultModel::Unchanged => UpsertResult::Unchanged,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::objects::ObjectCursor;
+    use domain::tenants::{TenantId, TenantScoped};
+
+    /// Rejects records without a phone, and fails the whole call when a record is marked as
+    /// failing to insert.
+    #[derive(Clone)]
+    struct Objects;
+
+    impl TenantScoped for Objects {
+        fn for_tenant(&self, _: &TenantId) -> Self {
+            self.clone()
+        }
+    }
+
+    #[tonic::async_trait]
+    impl ObjectRepository for Objects {
+        async fn find_all(&self) -> Result<Vec<ObjectModel>, DomainError> {
+            unimplemented!()
+        }
+
+        async fn count(&self) -> Result<u64, DomainError> {
+            unimplemented!()
+        }
+
+        async fn find_page(
+            &self,
+            _: Option<String>,
+            _: u64,
+        ) -> Result<Vec<ObjectModel>, DomainError> {
+            unimplemented!()
+        }
+
+        async fn find_batch(
+            &self,
+            _: Option<chrono::DateTime<chrono::FixedOffset>>,
+            _: Option<ObjectCursor>,
+            _: u64,
+        ) -> Result<Vec<(ObjectModel, ObjectCursor)>, DomainError> {
+            unimplemented!()
+        }
+
+        async fn get(&self, _: String) -> Result<Option<ObjectModel>, DomainError> {
+            unimplemented!()
+        }
+
+        async fn create(&self, _: HashMap<String, String>) -> Result<ObjectModel, DomainError> {
+            unimplemented!()
+        }
+
+        async fn create_many(
+            &self,
+            attributes: Vec<HashMap<String, String>>,
+        ) -> Result<Vec<Result<ObjectModel, DomainError>>, DomainError> {
+            if attributes.iter().any(|record| record.contains_key("fail")) {
+                return Err(DomainError::Unexpected("insert failed".to_string()));
+            }
+
+            Ok(attributes
+                .into_iter()
+                .map(|attributes| match attributes.contains_key("phone") {
+                    true => Ok(ObjectModel {
+                        id: "object".to_string(),
+                        tenant_id: TenantId::default(),
+                        attributes,
+                    }),
+                    false => Err(DomainError::InvalidArgument("phone".to_string())),
+                })
+                .collect())
+        }
+
+        async fn upsert(
+            &self,
+            _: String,
+            _: String,
+            _: HashMap<String, String>,
+        ) -> Result<(ObjectModel, UpsertResultModel), DomainError> {
+            unimplemented!()
+        }
+
+        async fn update(
+            &self,
+            _: String,
+            _: HashMap<String, String>,
+        ) -> Result<ObjectModel, DomainError> {
+            unimplemented!()
+        }
+
+        async fn patch(
+            &self,
+            _: String,
+            _: HashMap<String, String>,
+            _: Vec<String>,
+        ) -> Result<ObjectModel, DomainError> {
+            unimplemented!()
+        }
+
+        async fn delete(&self, _: String) -> Result<(), DomainError> {
+            unimplemented!()
+        }
+    }
+
+    fn record(key: &str) -> HashMap<String, String> {
+        HashMap::from([(key.to_string(), "000".to_string())])
+    }
+
+    fn failed_indexes(summary: &ImportObjectsResponse) -> Vec<u64> {
+        summary.errors.iter().map(|error| error.index).collect()
+    }
+
+    #[tokio::test]
+    async fn invalid_records_are_reported_at_their_index() {
+        let mut summary = ImportObjectsResponse::default();
+
+        ObjectsServerImpl::import_chunk(&Objects, vec![record("phone")], &mut summary).await;
+        ObjectsServerImpl::import_chunk(
+            &Objects,
+            vec![record("phone"), record("email"), record("phone")],
+            &mut summary,
+        )
+        .await;
+
+        assert_eq!(summary.received, 4);
+        assert_eq!(summary.imported, 3);
+        assert_eq!(failed_indexes(&summary), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn records_of_a_failed_chunk_are_retried_one_by_one() {
+        let mut summary = ImportObjectsResponse::default();
+
+        ObjectsServerImpl::import_chunk(
+            &Objects,
+            vec![
+                record("phone"),
+                record("fail"),
+                record("email"),
+                record("phone"),
+            ],
+            &mut summary,
+        )
+        .await;
+
+        assert_eq!(summary.received, 4);
+        assert_eq!(summary.imported, 2);
+        assert_eq!(failed_indexes(&summary), vec![1, 2]);
+        assert!(summary.errors[0].message.contains("insert failed"));
+    }
+}
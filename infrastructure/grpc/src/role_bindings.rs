@@ -0,0 +1,138 @@
+use crate::api::role_bindings_server::RoleBindings;
+use crate::api::{
+    CreateRoleBindingRequest, CreateRoleBindingResponse, DeleteRoleBindingRequest,
+    DeleteRoleBindingResponse, ListRoleBindingsRequest, ListRoleBindingsResponse, ListRolesRequest,
+    ListRolesResponse, Role, RoleBinding,
+};
+use crate::auth::authorize;
+use crate::error::handle_error;
+use crate::tenants::tenant;
+use crate::timestamp::to_timestamp;
+use domain::auth::{
+    Permission, Role as RoleModel, RoleBinding as RoleBindingModel, RoleBindingRepository,
+};
+use domain::error::DomainError;
+use tonic::{Request, Response, Status};
+
+#[derive(Debug)]
+pub struct RoleBindingsServerImpl<T: RoleBindingRepository> {
+    repository: T,
+}
+
+impl<T: RoleBindingRepository> RoleBindingsServerImpl<T> {
+    pub fn new(repository: T) -> Self {
+        Self { repository }
+    }
+}
+
+#[tonic::async_trait]
+impl<T: RoleBindingRepository> RoleBindings for RoleBindingsServerImpl<T> {
+    async fn list_roles(
+        &self,
+        request: Request<ListRolesRequest>,
+    ) -> Result<Response<ListRolesResponse>, Status> {
+        authorize(&request, Permission::AuthAdmin).map_err(handle_error)?;
+
+        let response = ListRolesResponse {
+            roles: RoleModel::ALL.into_iter().map(Into::into).collect(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn create_role_binding(
+        &self,
+        request: Request<CreateRoleBindingRequest>,
+    ) -> Result<Response<CreateRoleBindingResponse>, Status> {
+        authorize(&request, Permission::AuthAdmin).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        if request.subject.is_empty() {
+            return Err(handle_error(DomainError::InvalidArgument(
+                "subject must not be empty".to_string(),
+            )));
+        }
+        let role = request.role.parse().map_err(handle_error)?;
+
+        let result = repository
+            .create(request.subject, role)
+            .await
+            .map_err(handle_error)?;
+        tracing::info!(subject = %result.subject, role = result.role.as_str(), "bound role");
+
+        let response = CreateRoleBindingResponse {
+            role_binding: Some(result.into()),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn list_role_bindings(
+        &self,
+        request: Request<ListRoleBindingsRequest>,
+    ) -> Result<Response<ListRoleBindingsResponse>, Status> {
+        authorize(&request, Permission::AuthAdmin).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let subject = match request.subject.is_empty() {
+            true => None,
+            false => Some(request.subject),
+        };
+        let result = repository.find_all(subject).await.map_err(handle_error)?;
+
+        let response = ListRoleBindingsResponse {
+            role_bindings: result
+                .into_iter()
+                .map(Into::into)
+                .collect::<Vec<RoleBinding>>(),
+        };
+
+        Ok(Response::new(response))
+    }
+
+    async fn delete_role_binding(
+        &self,
+        request: Request<DeleteRoleBindingRequest>,
+    ) -> Result<Response<DeleteRoleBindingResponse>, Status> {
+        authorize(&request, Permission::AuthAdmin).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        repository
+            .delete(request.id.clone())
+            .await
+            .map_err(handle_error)?;
+        tracing::info!(role_binding_id = %request.id, "deleted role binding");
+
+        Ok(Response::new(DeleteRoleBindingResponse {}))
+    }
+}
+
+impl From<RoleModel> for Role {
+    fn from(value: RoleModel) -> Self {
+        Role {
+            name: value.as_str().to_string(),
+            permissions: value
+                .permissions()
+                .iter()
+                .map(|permission| permission.as_str().to_string())
+                .collect(),
+        }
+    }
+}
+
+impl From<RoleBindingModel> for RoleBinding {
+    fn from(value: RoleBindingModel) -> Self {
+        RoleBinding {
+            id: value.id,
+            subject: value.subject,
+            role: value.role.as_str().to_string(),
+            created_at: Some(to_timestamp(value.created_at)),
+        }
+    }
+}
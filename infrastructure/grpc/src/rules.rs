@@ -4,9 +4,13 @@ use crate::api::{
     DeleteRuleRequest, DeleteRuleResponse, Equals, GetRuleRequest, GetRuleResponse,
     GetRulesRequest, GetRulesResponse, Regex, Rule, UpdateRuleRequest, UpdateRuleResponse,
 };
+use crate::auth::authorize;
 use crate::error::handle_error;
+use crate::tenants::tenant;
+use domain::auth::Permission;
+use domain::error::DomainError;
 use domain::rules::{GenerationRule, Rule as RuleModel, RuleRepository};
-use tonic::{Code, Request, Response, Status};
+use tonic::{Request, Response, Status};
 
 #[derive(Debug)]
 pub struct RulesServerImpl<T: RuleRepository> {
@@ -23,9 +27,13 @@ impl<T: RuleRepository> RulesServerImpl<T> {
 impl<T: RuleRepository> Rules for RulesServerImpl<T> {
     async fn get_rules(
         &self,
-        _request: Request<GetRulesRequest>,
+        request: Request<GetRulesRequest>,
     ) -> Result<Response<GetRulesResponse>, Status> {
-        let result = self.repository.find_all().await.map_err(handle_error)?;
+        authorize(&request, Permission::RulesRead).map_err(handle_error)?;
+        let repository = self
+            .repository
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let result = repository.find_all().await.map_err(handle_error)?;
 
         let response = GetRulesResponse {
             rules: result.into_iter().map(Into::into).collect::<Vec<Rule>>(),
@@ -38,12 +46,12 @@ impl<T: RuleRepository> Rules for RulesServerImpl<T> {
         &self,
         request: Request<GetRuleRequest>,
     ) -> Result<Response<GetRuleResponse>, Status> {
-        let request = request.into_inner();
-        let result = self
+        authorize(&request, Permission::RulesRead).map_err(handle_error)?;
+        let repository = self
             .repository
-            .get(request.id)
-            .await
-            .map_err(handle_error)?;
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let result = repository.get(request.id).await.map_err(handle_error)?;
 
         let response = GetRuleResponse {
             rule: result.map(Into::into),
@@ -56,20 +64,24 @@ impl<T: RuleRepository> Rules for RulesServerImpl<T> {
         &self,
         request: Request<AddRuleRequest>,
     ) -> Result<Response<AddRuleResponse>, Status> {
-        let request = request.into_inner();
-        let result = self
+        authorize(&request, Permission::RulesWrite).map_err(handle_error)?;
+        let repository = self
             .repository
-            .create(
-                request.field_id,
-                request.rule_type.map(Into::into).ok_or(Status::new(
-                    Code::InvalidArgument,
-                    "InvalidArgument".to_string(),
-                ))?,
-            )
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let rule_type: GenerationRule = request.rule_type.map(Into::into).ok_or_else(|| {
+            handle_error(DomainError::InvalidArgument(
+                "rule_type must be set".to_string(),
+            ))
+        })?;
+        rule_type.validate().map_err(handle_error)?;
+        let (result, job_id) = repository
+            .create(request.field_id, rule_type)
             .await
             .map_err(handle_error)?;
         let response = AddRuleResponse {
             rule: Some(result.into()),
+            job_id,
         };
 
         Ok(Response::new(response))
@@ -79,21 +91,24 @@ impl<T: RuleRepository> Rules for RulesServerImpl<T> {
         &self,
         request: Request<UpdateRuleRequest>,
     ) -> Result<Response<UpdateRuleResponse>, Status> {
-        let request = request.into_inner();
-        let result = self
+        authorize(&request, Permission::RulesWrite).map_err(handle_error)?;
+        let repository = self
             .repository
-            .update(
-                request.id,
-                request.field_id,
-                request.rule_type.map(Into::into).ok_or(Status::new(
-                    Code::InvalidArgument,
-                    "InvalidArgument".to_string(),
-                ))?,
-            )
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let rule_type: GenerationRule = request.rule_type.map(Into::into).ok_or_else(|| {
+            handle_error(DomainError::InvalidArgument(
+                "rule_type must be set".to_string(),
+            ))
+        })?;
+        rule_type.validate().map_err(handle_error)?;
+        let (result, job_id) = repository
+            .update(request.id, request.field_id, rule_type)
             .await
             .map_err(handle_error)?;
         let response = UpdateRuleResponse {
             rule: Some(result.into()),
+            job_id,
         };
 
         Ok(Response::new(response))
@@ -103,13 +118,13 @@ impl<T: RuleRepository> Rules for RulesServerImpl<T> {
         &self,
         request: Request<DeleteRuleRequest>,
     ) -> Result<Response<DeleteRuleResponse>, Status> {
-        let request = request.into_inner();
-        let _ = self
+        authorize(&request, Permission::RulesWrite).map_err(handle_error)?;
+        let repository = self
             .repository
-            .delete(request.id)
-            .await
-            .map_err(handle_error)?;
-        let response = DeleteRuleResponse {};
+            .for_tenant(tenant(&request).map_err(handle_error)?);
+        let request = request.into_inner();
+        let job_id = repository.delete(request.id).await.map_err(handle_error)?;
+        let response = DeleteRuleResponse { job_id };
 
         Ok(Response::new(response))
     }
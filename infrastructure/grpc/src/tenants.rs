@@ -0,0 +1,119 @@
+use domain::auth::{AuthMethod, Principal};
+use domain::error::DomainError;
+use domain::tenants::TenantId;
+use tonic::codegen::http::HeaderMap;
+
+/// Metadata naming the tenant of an RPC.
+pub const HEADER: &str = "x-tenant-id";
+
+/// Returns the tenant of the credentials, or the default tenant when they have none. Naming
+/// another tenant in the metadata is denied, except for anonymous callers, who may choose any
+/// tenant as authentication is disabled.
+pub fn resolve(principal: &Principal, headers: &HeaderMap) -> Result<TenantId, DomainError> {
+    let requested = headers
+        .get(HEADER)
+        .map(|value| {
+            let value = value.to_str().map_err(|_| {
+                DomainError::InvalidArgument(format!("invalid [{}] metadata", HEADER))
+            })?;
+            TenantId::parse(value)
+        })
+        .transpose()?;
+
+    if principal.method == AuthMethod::Anonymous {
+        return Ok(requested.unwrap_or_default());
+    }
+    let own = principal.tenant_id.clone().unwrap_or_default();
+    match requested {
+        Some(requested) if requested != own => Err(DomainError::PermissionDenied(format!(
+            "[{}] cannot access the tenant [{}]",
+            principal.subject, requested
+        ))),
+        _ => Ok(own),
+    }
+}
+
+/// Returns the tenant attached by [`AuthLayer`](crate::auth::AuthLayer).
+pub fn tenant<T>(request: &tonic::Request<T>) -> Result<&TenantId, DomainError> {
+    request
+        .extensions()
+        .get::<TenantId>()
+        .ok_or_else(|| DomainError::Unauthenticated("the request has no tenant".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(method: AuthMethod, tenant_id: Option<&str>) -> Principal {
+        Principal {
+            subject: "user".to_string(),
+            method,
+            roles: vec![],
+            tenant_id: tenant_id.map(|tenant_id| TenantId::parse(tenant_id).unwrap()),
+        }
+    }
+
+    fn headers(tenant_id: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(tenant_id) = tenant_id {
+            headers.insert(HEADER, tenant_id.parse().unwrap());
+        }
+
+        headers
+    }
+
+    #[test]
+    fn tenants_are_resolved_from_the_credentials_and_the_metadata() {
+        let default = TenantId::default();
+        let cases = [
+            // the tenant of the credentials
+            (AuthMethod::Jwt, Some("acme"), None, Some("acme")),
+            (AuthMethod::ApiKey, Some("acme"), Some("acme"), Some("acme")),
+            (AuthMethod::Jwt, Some("acme"), Some("other"), None),
+            // credentials without a tenant are confined to the default tenant
+            (AuthMethod::Jwt, None, None, Some(default.as_str())),
+            (
+                AuthMethod::Jwt,
+                None,
+                Some(default.as_str()),
+                Some(default.as_str()),
+            ),
+            (AuthMethod::Jwt, None, Some("other"), None),
+            // anonymous callers choose any tenant
+            (AuthMethod::Anonymous, None, Some("other"), Some("other")),
+            (AuthMethod::Anonymous, None, None, Some(default.as_str())),
+        ];
+        for (method, own, requested, expected) in cases {
+            let result = resolve(&principal(method, own), &headers(requested));
+
+            match expected {
+                Some(expected) => assert_eq!(
+                    result.unwrap().as_str(),
+                    expected,
+                    "{:?} {:?} {:?}",
+                    method,
+                    own,
+                    requested
+                ),
+                None => assert!(
+                    matches!(result, Err(DomainError::PermissionDenied(_))),
+                    "{:?} {:?} {:?}",
+                    method,
+                    own,
+                    requested
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_tenants_are_rejected() {
+        let result = resolve(
+            &principal(AuthMethod::Anonymous, None),
+            &headers(Some("Not A Tenant")),
+        );
+
+        assert!(matches!(result, Err(DomainError::InvalidArgument(_))));
+    }
+}
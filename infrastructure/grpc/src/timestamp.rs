@@ -0,0 +1,16 @@
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use prost_types::Timestamp;
+
+pub fn to_timestamp(value: DateTime<FixedOffset>) -> Timestamp {
+    Timestamp {
+        seconds: value.timestamp(),
+        nanos: value.timestamp_subsec_nanos() as i32,
+    }
+}
+
+pub fn from_timestamp(value: Timestamp) -> Option<DateTime<FixedOffset>> {
+    let nanos = u32::try_from(value.nanos).ok()?;
+    let value = Utc.timestamp_opt(value.seconds, nanos).single()?;
+
+    Some(value.with_timezone(&FixedOffset::east_opt(0).unwrap()))
+}
@@ -0,0 +1,314 @@
+use anyhow::{bail, Context, Result};
+use rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient};
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use rustls_pemfile::Item;
+use std::fs::{self, File};
+use std::io::{self, BufReader, IoSlice};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::Poll;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tonic::transport::server::Connected;
+
+/// How often the certificate files are checked for changes.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+/// Time given to a client to complete the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Pause after a failed accept, e.g. when the process runs out of file descriptors.
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug)]
+pub struct TlsOptions {
+    /// PEM certificate chain of the server.
+    pub cert_path: PathBuf,
+    /// PEM private key of the server.
+    pub key_path: PathBuf,
+    /// PEM bundle of the CAs client certificates are verified against. Clients are not asked
+    /// for a certificate when it is not set.
+    pub client_ca_path: Option<PathBuf>,
+    /// Accepts clients without a certificate even though `client_ca_path` is set.
+    pub client_cert_optional: bool,
+}
+
+/// Server TLS config that is reloaded when one of its files changes, so certificates can be
+/// rotated without a restart. Connections keep the config they were accepted with.
+#[derive(Clone)]
+pub struct TlsReloader {
+    options: TlsOptions,
+    config: Arc<RwLock<Arc<ServerConfig>>>,
+}
+
+impl TlsReloader {
+    pub fn new(options: TlsOptions) -> Result<Self> {
+        let config = load(&options)?;
+
+        Ok(Self {
+            options,
+            config: Arc::new(RwLock::new(Arc::new(config))),
+        })
+    }
+
+    /// Checks the files until `shutdown` is cancelled and reloads the config when one of them
+    /// changed. An invalid file is logged and the previous config kept.
+    pub async fn run(&self, shutdown: &CancellationToken) {
+        let mut modified = self.modified();
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(RELOAD_INTERVAL) => {}
+                _ = shutdown.cancelled() => return,
+            }
+
+            let current = self.modified();
+            if current == modified {
+                continue;
+            }
+            modified = current;
+            match load(&self.options) {
+                Ok(config) => {
+                    *self.config.write().expect("TLS config lock is poisoned") = Arc::new(config);
+                    tracing::info!("reloaded the TLS certificates");
+                }
+                Err(e) => tracing::warn!(
+                    error = format!("{:#}", e),
+                    "failed to reload the TLS certificates, keeping the previous ones"
+                ),
+            }
+        }
+    }
+
+    fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(
+            self.config
+                .read()
+                .expect("TLS config lock is poisoned")
+                .clone(),
+        )
+    }
+
+    // symbolic links are followed, so swapping the target of a mounted secret counts
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        [Some(&self.options.cert_path), Some(&self.options.key_path)]
+            .into_iter()
+            .chain([self.options.client_ca_path.as_ref()])
+            .flatten()
+            .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
+
+/// Accepts TLS connections on `listener` until `shutdown` is cancelled.
+/// Handshakes run concurrently so that a slow client does not hold up the others, and the
+/// failed ones are dropped.
+pub fn incoming(
+    listener: TcpListener,
+    reloader: TlsReloader,
+    shutdown: CancellationToken,
+) -> ReceiverStream<io::Result<TlsConnection>> {
+    let (sender, receiver) = mpsc::channel(64);
+    tokio::spawn(async move {
+        loop {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = sender.closed() => return,
+                _ = shutdown.cancelled() => return,
+            };
+            let (stream, remote_addr) = match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!(error = %e, "failed to accept a connection");
+                    tokio::time::sleep(ACCEPT_ERROR_DELAY).await;
+                    continue;
+                }
+            };
+
+            let acceptor = reloader.acceptor();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        let _ = sender
+                            .send(Ok(TlsConnection::new(stream, remote_addr)))
+                            .await;
+                    }
+                    Ok(Err(e)) => tracing::debug!(%remote_addr, error = %e, "TLS handshake failed"),
+                    Err(_) => tracing::debug!(%remote_addr, "TLS handshake timed out"),
+                }
+            });
+        }
+    });
+
+    ReceiverStream::new(receiver)
+}
+
+/// Attached to the extensions of every request received on a TLS connection.
+#[derive(Clone, Debug)]
+pub struct TlsConnectInfo {
+    pub remote_addr: SocketAddr,
+    /// Subject of the client certificate, e.g. `CN=ingestion, O=Example`. The certificate was
+    /// verified against the client CA bundle during the handshake.
+    pub client_subject: Option<String>,
+}
+
+/// Returns the subject of the client certificate of the connection `request` was received on.
+pub fn client_subject<T>(request: &tonic::Request<T>) -> Option<&str> {
+    request
+        .extensions()
+        .get::<TlsConnectInfo>()
+        .and_then(|info| info.client_subject.as_deref())
+}
+
+/// Connection yielded by [`incoming`].
+pub struct TlsConnection {
+    stream: TlsStream<TcpStream>,
+    info: TlsConnectInfo,
+}
+
+impl TlsConnection {
+    fn new(stream: TlsStream<TcpStream>, remote_addr: SocketAddr) -> Self {
+        let client_subject = stream
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certificates| certificates.first())
+            .and_then(|certificate| {
+                x509_parser::parse_x509_certificate(&certificate.0)
+                    .ok()
+                    .map(|(_, certificate)| certificate.subject().to_string())
+            });
+
+        Self {
+            stream,
+            info: TlsConnectInfo {
+                remote_addr,
+                client_subject,
+            },
+        }
+    }
+}
+
+impl Connected for TlsConnection {
+    type ConnectInfo = TlsConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.info.clone()
+    }
+}
+
+impl AsyncRead for TlsConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+fn load(options: &TlsOptions) -> Result<ServerConfig> {
+    let certificates = read_certificates(&options.cert_path)?;
+    let key = read_private_key(&options.key_path)?;
+
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &options.client_ca_path {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for certificate in read_certificates(path)? {
+                roots
+                    .add(&certificate)
+                    .with_context(|| format!("invalid CA certificate: [{}]", path.display()))?;
+            }
+            let verifier = if options.client_cert_optional {
+                AllowAnyAnonymousOrAuthenticatedClient::new(roots)
+            } else {
+                AllowAnyAuthenticatedClient::new(roots)
+            };
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut config = builder
+        .with_single_cert(certificates, key)
+        .with_context(|| {
+            format!(
+                "invalid certificate or key: [{}], [{}]",
+                options.cert_path.display(),
+                options.key_path.display()
+            )
+        })?;
+    // gRPC requires HTTP/2, the gateway also serves HTTP/1.1
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(config)
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    let file = File::open(path)
+        .with_context(|| format!("failed to read certificate file: [{}]", path.display()))?;
+    let certificates = rustls_pemfile::certs(&mut BufReader::new(file))
+        .with_context(|| format!("invalid certificate file: [{}]", path.display()))?;
+    if certificates.is_empty() {
+        bail!("no certificate in [{}]", path.display());
+    }
+
+    Ok(certificates.into_iter().map(Certificate).collect())
+}
+
+fn read_private_key(path: &Path) -> Result<PrivateKey> {
+    let file = File::open(path)
+        .with_context(|| format!("failed to read key file: [{}]", path.display()))?;
+    let mut reader = BufReader::new(file);
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)
+        .with_context(|| format!("invalid key file: [{}]", path.display()))?
+    {
+        if let Item::RSAKey(key) | Item::PKCS8Key(key) | Item::ECKey(key) = item {
+            return Ok(PrivateKey(key));
+        }
+    }
+
+    bail!("no private key in [{}]", path.display())
+}
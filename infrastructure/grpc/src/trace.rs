@@ -0,0 +1,96 @@
+use crate::tls::TlsConnectInfo;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use telemetry::request_id;
+use tonic::codegen::http::header::HeaderValue;
+use tonic::codegen::http::{Request, Response};
+use tonic::Code;
+use tower::{Layer, Service};
+use tracing::Instrument;
+
+/// Runs every RPC in a span carrying its request id, which is taken from the `x-request-id`
+/// metadata or generated, and echoed in the response metadata.
+/// Events written to the outbox during the RPC carry the request id to the worker.
+/// `AuthLayer` records the caller as `principal` and its tenant as `tenant_id`.
+/// On TLS connections, the subject of the client certificate is recorded as `client_subject`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TraceLayer;
+
+impl<S> Layer<S> for TraceLayer {
+    type Service = TraceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceService { inner }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TraceService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for TraceService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: std::fmt::Display,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let request_id = request
+            .headers()
+            .get(request_id::HEADER)
+            .and_then(|id| id.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= request_id::MAX_LEN)
+            .map_or_else(request_id::generate, str::to_string);
+        let span = tracing::info_span!(
+            "rpc",
+            method = request.uri().path(),
+            request_id = %request_id,
+            principal = tracing::field::Empty,
+            tenant_id = tracing::field::Empty,
+            client_subject = tracing::field::Empty,
+            code = tracing::field::Empty,
+        );
+        if let Some(subject) = request
+            .extensions()
+            .get::<TlsConnectInfo>()
+            .and_then(|info| info.client_subject.as_deref())
+        {
+            span.record("client_subject", subject);
+        }
+        let started_at = Instant::now();
+        let response = span.in_scope(|| self.inner.call(request));
+        let scoped_id = request_id.clone();
+
+        let future = async move {
+            let mut response = response.await;
+            match &mut response {
+                Ok(response) => {
+                    let code = response
+                        .headers()
+                        .get("grpc-status")
+                        .map_or(Code::Ok, |code| Code::from_bytes(code.as_bytes()));
+                    tracing::Span::current().record("code", format!("{:?}", code));
+                    tracing::info!(elapsed = ?started_at.elapsed(), "finished");
+                    if let Ok(value) = HeaderValue::from_str(&request_id) {
+                        response.headers_mut().insert(request_id::HEADER, value);
+                    }
+                }
+                Err(e) => tracing::error!(error = %e, "failed"),
+            }
+
+            response
+        };
+        Box::pin(request_id::scope(scoped_id, future.instrument(span)))
+    }
+}
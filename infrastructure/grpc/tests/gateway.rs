@@ -0,0 +1,12 @@
+use grpc::gateway;
+use grpc::API_DESCRIPTOR_SET;
+
+#[test]
+fn openapi_document_is_up_to_date() {
+    let document = gateway::openapi(API_DESCRIPTOR_SET).unwrap();
+
+    assert!(
+        document == include_str!("../../../docs/openapi.json"),
+        "docs/openapi.json is outdated, run `cargo make openapi`"
+    );
+}
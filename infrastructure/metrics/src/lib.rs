@@ -0,0 +1,142 @@
+use anyhow::Result;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    Encoder, Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, TextEncoder,
+};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+static RPC_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "grpc_server_requests_total",
+        "gRPC requests handled, by method and status code.",
+        &["method", "code"]
+    )
+    .unwrap()
+});
+
+static RPC_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "grpc_server_request_duration_seconds",
+        "Time until the response headers of a gRPC request are sent.",
+        &["method"]
+    )
+    .unwrap()
+});
+
+static REPOSITORY_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "repository_query_duration_seconds",
+        "Duration of repository operations, including their transactions.",
+        &["repository", "operation"]
+    )
+    .unwrap()
+});
+
+static KEYS_GENERATED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("worker_keys_generated_total", "Keys written by the worker.").unwrap()
+});
+
+static MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "worker_messages_total",
+        "Messages settled by the worker, by outcome.",
+        &["outcome"]
+    )
+    .unwrap()
+});
+
+static MESSAGE_LAG: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "worker_message_lag_seconds",
+        "Time from publishing a message until the worker starts handling it.",
+        vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0]
+    )
+    .unwrap()
+});
+
+/// How the worker settled a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageOutcome {
+    Ack,
+    Nack,
+    DeadLetter,
+}
+
+impl MessageOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MessageOutcome::Ack => "ack",
+            MessageOutcome::Nack => "nack",
+            MessageOutcome::DeadLetter => "dead_letter",
+        }
+    }
+}
+
+pub fn observe_rpc(method: &str, code: &str, elapsed: Duration) {
+    RPC_REQUESTS.with_label_values(&[method, code]).inc();
+    RPC_DURATION
+        .with_label_values(&[method])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Records the duration of a repository operation when the returned timer is dropped.
+pub fn time_repository(repository: &str, operation: &str) -> HistogramTimer {
+    REPOSITORY_DURATION
+        .with_label_values(&[repository, operation])
+        .start_timer()
+}
+
+pub fn add_keys_generated(count: usize) {
+    KEYS_GENERATED.inc_by(count as u64);
+}
+
+pub fn count_message(outcome: MessageOutcome) {
+    MESSAGES.with_label_values(&[outcome.as_str()]).inc();
+}
+
+pub fn observe_message_lag(lag: Duration) {
+    MESSAGE_LAG.observe(lag.as_secs_f64());
+}
+
+/// Serves the metrics in the Prometheus text format on `GET /metrics` until `shutdown` is cancelled.
+pub async fn serve(addr: SocketAddr, shutdown: CancellationToken) -> Result<()> {
+    let make_service =
+        make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle_request)) });
+    Server::try_bind(&addr)?
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await?;
+
+    Ok(())
+}
+
+async fn handle_request(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::GET || request.uri().path() != "/metrics" {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(response);
+    }
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        let mut response = Response::new(Body::from(e.to_string()));
+        *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        return Ok(response);
+    }
+
+    let mut response = Response::new(Body::from(buffer));
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(prometheus::TEXT_FORMAT),
+    );
+
+    Ok(response)
+}
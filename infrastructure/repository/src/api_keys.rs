@@ -0,0 +1,92 @@
+use database::client::api_keys::{PostgresApiKeyCommand, PostgresApiKeyQuery};
+use database::error::transaction_error;
+use domain::auth::{ApiKey, ApiKeyRepository};
+use domain::error::DomainError;
+use domain::tenants::{TenantId, TenantScoped};
+use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+
+#[derive(Debug, Clone)]
+pub struct ApiKeyRepositoryImpl {
+    conn: DatabaseConnection,
+    tenant_id: TenantId,
+}
+
+impl ApiKeyRepositoryImpl {
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self {
+            conn,
+            tenant_id: TenantId::default(),
+        }
+    }
+}
+
+impl TenantScoped for ApiKeyRepositoryImpl {
+    fn for_tenant(&self, tenant_id: &TenantId) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            tenant_id: tenant_id.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for ApiKeyRepositoryImpl {
+    #[tracing::instrument(skip_all)]
+    async fn find_active_by_hash(&self, key_hash: String) -> Result<Option<ApiKey>, DomainError> {
+        let _timer = metrics::time_repository("api_keys", "find_active_by_hash");
+        let client = PostgresApiKeyQuery::new(&self.conn, &self.tenant_id);
+        let api_key = client.find_active_by_hash(key_hash).await?;
+
+        Ok(api_key.map(Into::into))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_all(&self) -> Result<Vec<ApiKey>, DomainError> {
+        let _timer = metrics::time_repository("api_keys", "find_all");
+        let client = PostgresApiKeyQuery::new(&self.conn, &self.tenant_id);
+        let api_keys = client.find_all().await?;
+
+        Ok(api_keys.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create(&self, name: String, key_hash: String) -> Result<ApiKey, DomainError> {
+        let _timer = metrics::time_repository("api_keys", "create");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, ApiKey, DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresApiKeyCommand::new(txn, &tenant_id);
+                    let result = client.create(name, key_hash).await?;
+
+                    Ok(result.into())
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn revoke(&self, id: String) -> Result<ApiKey, DomainError> {
+        let _timer = metrics::time_repository("api_keys", "revoke");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, ApiKey, DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresApiKeyCommand::new(txn, &tenant_id);
+                    let result = client.revoke(id).await?;
+
+                    Ok(result.into())
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,95 @@
+use database::client::dead_letters::{PostgresDeadLetterCommand, PostgresDeadLetterQuery};
+use database::error::transaction_error;
+use domain::dead_letters::{DeadLetter, DeadLetterRepository, NewDeadLetter};
+use domain::error::DomainError;
+use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+
+#[derive(Debug, Clone)]
+pub struct DeadLetterRepositoryImpl {
+    conn: DatabaseConnection,
+}
+
+impl DeadLetterRepositoryImpl {
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait]
+impl DeadLetterRepository for DeadLetterRepositoryImpl {
+    #[tracing::instrument(skip_all)]
+    async fn find_all(&self, include_replayed: bool) -> Result<Vec<DeadLetter>, DomainError> {
+        let _timer = metrics::time_repository("dead_letters", "find_all");
+        let client = PostgresDeadLetterQuery::new(&self.conn);
+        let dead_letters = client.find_all(include_replayed).await?;
+
+        Ok(dead_letters.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get(&self, id: i64) -> Result<Option<DeadLetter>, DomainError> {
+        let _timer = metrics::time_repository("dead_letters", "get");
+        let client = PostgresDeadLetterQuery::new(&self.conn);
+        let dead_letter = client.find_by_id(id).await?;
+
+        Ok(dead_letter.map(Into::into))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create(&self, dead_letter: NewDeadLetter) -> Result<DeadLetter, DomainError> {
+        let _timer = metrics::time_repository("dead_letters", "create");
+        let result = self
+            .conn
+            .transaction::<_, DeadLetter, DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresDeadLetterCommand::new(txn);
+                    let result = client.create(dead_letter).await?;
+
+                    Ok(result.into())
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn claim_replay(&self, id: i64) -> Result<DeadLetter, DomainError> {
+        let _timer = metrics::time_repository("dead_letters", "claim_replay");
+        let result = self
+            .conn
+            .transaction::<_, DeadLetter, DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresDeadLetterCommand::new(txn);
+                    let result = client.claim_replay(id).await?;
+
+                    Ok(result.into())
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn release_replay(&self, id: i64) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("dead_letters", "release_replay");
+        let _ = self
+            .conn
+            .transaction::<_, (), DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresDeadLetterCommand::new(txn);
+                    client.release_replay(id).await?;
+
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(())
+    }
+}
@@ -1,59 +1,79 @@
 use database::client::fields::{PostgresFieldCommand, PostgresFieldQuery};
 use database::entities::fields;
+use database::error::transaction_error;
 use domain::error::DomainError;
 use domain::fields::{Field, FieldRepository};
+use domain::tenants::{TenantId, TenantScoped};
 use sea_orm::prelude::async_trait::async_trait;
 use sea_orm::{DatabaseConnection, TransactionTrait};
 
 #[derive(Debug, Clone)]
 pub struct FieldRepositoryImpl {
     conn: DatabaseConnection,
+    tenant_id: TenantId,
 }
 
 impl FieldRepositoryImpl {
     pub fn new(conn: DatabaseConnection) -> Self {
-        Self { conn }
+        Self {
+            conn,
+            tenant_id: TenantId::default(),
+        }
+    }
+}
+
+impl TenantScoped for FieldRepositoryImpl {
+    fn for_tenant(&self, tenant_id: &TenantId) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            tenant_id: tenant_id.clone(),
+        }
     }
 }
 
 #[async_trait]
 impl FieldRepository for FieldRepositoryImpl {
+    #[tracing::instrument(skip_all)]
     async fn find_all(&self) -> Result<Vec<Field>, DomainError> {
-        let client = PostgresFieldQuery::new(&self.conn);
-        let response = client
-            .find_all()
-            .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        let _timer = metrics::time_repository("fields", "find_all");
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let response = client.find_all().await?;
 
         Ok(response.into_iter().map(Into::into).collect())
     }
 
+    #[tracing::instrument(skip_all)]
     async fn create(&self, data_label: String, label: String) -> Result<Field, DomainError> {
+        let _timer = metrics::time_repository("fields", "create");
+        let tenant_id = self.tenant_id.clone();
         let response = self
             .conn
             .transaction::<_, fields::Model, DomainError>(|txn| {
                 Box::pin(async move {
-                    let client = PostgresFieldCommand::new(txn);
+                    let client = PostgresFieldCommand::new(txn, &tenant_id);
                     client.create(data_label, label).await
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
         Ok(response.into())
     }
 
+    #[tracing::instrument(skip_all)]
     async fn update(&self, id: String, label: String) -> Result<Field, DomainError> {
+        let _timer = metrics::time_repository("fields", "update");
+        let tenant_id = self.tenant_id.clone();
         let response = self
             .conn
             .transaction::<_, fields::Model, DomainError>(|txn| {
                 Box::pin(async move {
-                    let client = PostgresFieldCommand::new(txn);
+                    let client = PostgresFieldCommand::new(txn, &tenant_id);
                     client.update(id, label).await
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
         Ok(response.into())
     }
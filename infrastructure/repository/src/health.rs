@@ -0,0 +1,42 @@
+use database::error::db_error;
+use domain::error::DomainError;
+use domain::health::HealthRepository;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::{ConnectionTrait, DatabaseConnection, Statement};
+
+#[derive(Debug, Clone)]
+pub struct HealthRepositoryImpl {
+    conn: DatabaseConnection,
+}
+
+impl HealthRepositoryImpl {
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait]
+impl HealthRepository for HealthRepositoryImpl {
+    async fn check(&self) -> Result<(), DomainError> {
+        self.conn
+            .execute(Statement::from_string(
+                self.conn.get_database_backend(),
+                "SELECT 1".to_string(),
+            ))
+            .await
+            .map_err(db_error)?;
+
+        let pending = Migrator::get_pending_migrations(&self.conn)
+            .await
+            .map_err(db_error)?;
+        if !pending.is_empty() {
+            return Err(DomainError::FailedPrecondition(format!(
+                "{} migrations are pending",
+                pending.len()
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use database::client::jobs::{PostgresJobCommand, PostgresJobQuery};
+use database::error::transaction_error;
+use domain::error::DomainError;
+use domain::jobs::{Job, JobRepository, JobStatus};
+use domain::tenants::{TenantId, TenantScoped};
+use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+
+#[derive(Debug, Clone)]
+pub struct JobRepositoryImpl {
+    conn: DatabaseConnection,
+    tenant_id: TenantId,
+}
+
+impl JobRepositoryImpl {
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self {
+            conn,
+            tenant_id: TenantId::default(),
+        }
+    }
+}
+
+impl TenantScoped for JobRepositoryImpl {
+    fn for_tenant(&self, tenant_id: &TenantId) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            tenant_id: tenant_id.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl JobRepository for JobRepositoryImpl {
+    #[tracing::instrument(skip_all)]
+    async fn get(&self, id: String) -> Result<Option<Job>, DomainError> {
+        let _timer = metrics::time_repository("jobs", "get");
+        let client = PostgresJobQuery::new(&self.conn, &self.tenant_id);
+        let job = client.find_by_id(id).await?;
+
+        Ok(job.map(Into::into))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_all(
+        &self,
+        rule_id: Option<String>,
+        status: Option<JobStatus>,
+        after: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<Job>, DomainError> {
+        let _timer = metrics::time_repository("jobs", "find_all");
+        let client = PostgresJobQuery::new(&self.conn, &self.tenant_id);
+        let after = match after {
+            // `after` comes from a page token, which is malformed rather than missing when unknown
+            Some(id) => {
+                let job = client.find_by_id(id.clone()).await?.ok_or_else(|| {
+                    DomainError::InvalidArgument(format!("unknown page_token [{}]", id))
+                })?;
+                Some((job.created_at, job.id))
+            }
+            None => None,
+        };
+        let jobs = client.find_all(rule_id, status, after, limit).await?;
+
+        Ok(jobs.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn cancel(&self, id: String) -> Result<Job, DomainError> {
+        let _timer = metrics::time_repository("jobs", "cancel");
+        let client = PostgresJobQuery::new(&self.conn, &self.tenant_id);
+        if client.find_by_id(id.clone()).await?.is_none() {
+            return Err(DomainError::NotFound(id));
+        }
+
+        let job_id = id.clone();
+        let tenant_id = self.tenant_id.clone();
+        self.conn
+            .transaction::<_, (), DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresJobCommand::new(txn, &tenant_id);
+                    client.finish(job_id, JobStatus::Cancelled, None).await
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        let job = client
+            .find_by_id(id.clone())
+            .await?
+            .ok_or(DomainError::NotFound(id))?;
+
+        Ok(job.into())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn start(&self, id: String, total_objects: Option<u64>) -> Result<bool, DomainError> {
+        let _timer = metrics::time_repository("jobs", "start");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, bool, DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresJobCommand::new(txn, &tenant_id);
+                    client.start(id, total_objects.map(|v| v as i64)).await
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn add_progress(
+        &self,
+        id: String,
+        processed_objects: u64,
+        skipped_objects: u64,
+    ) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("jobs", "add_progress");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, (), DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresJobCommand::new(txn, &tenant_id);
+                    client
+                        .add_progress(id, processed_objects as i64, skipped_objects as i64)
+                        .await
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn finish(
+        &self,
+        id: String,
+        status: JobStatus,
+        error: Option<String>,
+    ) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("jobs", "finish");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, (), DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresJobCommand::new(txn, &tenant_id);
+                    client.finish(id, status, error).await
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+}
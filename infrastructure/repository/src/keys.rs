@@ -1,79 +1,280 @@
 use anyhow::Result;
-use database::client::keys::PostgresKeyCommand;
+use database::client::fields::PostgresFieldQuery;
+use database::client::key_sync_checkpoints::{
+    PostgresKeySyncCheckpointCommand, PostgresKeySyncCheckpointQuery,
+};
+use database::client::keys::{PostgresKeyCommand, PostgresKeyQuery};
+use database::client::objects::PostgresObjectQuery;
+use database::client::processed_messages::{
+    PostgresProcessedMessageCommand, PostgresProcessedMessageQuery,
+};
+use database::error::transaction_error;
+use database::to_object;
 use domain::error::DomainError;
-use domain::keys::KeyRepository;
+use domain::keys::{Key, KeyCursor, KeyRepository};
+use domain::objects::Object;
+use domain::tenants::{TenantId, TenantScoped};
 use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::prelude::DateTimeWithTimeZone;
 use sea_orm::{DatabaseConnection, TransactionTrait};
 
 #[derive(Debug, Clone)]
 pub struct KeyRepositoryImpl {
     conn: DatabaseConnection,
+    tenant_id: TenantId,
 }
 
 impl KeyRepositoryImpl {
     pub fn new(conn: DatabaseConnection) -> Self {
-        Self { conn }
+        Self {
+            conn,
+            tenant_id: TenantId::default(),
+        }
+    }
+}
+
+impl TenantScoped for KeyRepositoryImpl {
+    fn for_tenant(&self, tenant_id: &TenantId) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            tenant_id: tenant_id.clone(),
+        }
     }
 }
 
 #[async_trait]
 impl KeyRepository for KeyRepositoryImpl {
-    async fn create_by_rule(
+    #[tracing::instrument(skip_all)]
+    async fn find_by_object(&self, object_id: String) -> Result<Vec<Key>, DomainError> {
+        let _timer = metrics::time_repository("keys", "find_by_object");
+        let client = PostgresKeyQuery::new(&self.conn, &self.tenant_id);
+        let keys = client.find_by_object_id(object_id).await?;
+
+        Ok(keys.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_by_rule(
+        &self,
+        rule_id: String,
+        after: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<Key>, DomainError> {
+        let _timer = metrics::time_repository("keys", "find_by_rule");
+        let client = PostgresKeyQuery::new(&self.conn, &self.tenant_id);
+        let keys = client.find_by_rule_id(rule_id, after, limit).await?;
+
+        Ok(keys.into_iter().map(Into::into).collect())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_objects_by_key(
+        &self,
+        rule_id: String,
+        key: String,
+    ) -> Result<Vec<Object>, DomainError> {
+        let _timer = metrics::time_repository("keys", "find_objects_by_key");
+        let client = PostgresObjectQuery::new(&self.conn, &self.tenant_id);
+        let objects = client.find_by_key(rule_id, key).await?;
+
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
+
+        objects
+            .into_iter()
+            .map(|object| to_object(object, fields.clone()))
+            .collect::<Result<Vec<_>, _>>()
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_batch(
+        &self,
+        updated_since: Option<DateTimeWithTimeZone>,
+        after: Option<KeyCursor>,
+        limit: u64,
+    ) -> Result<Vec<(Key, KeyCursor)>, DomainError> {
+        let _timer = metrics::time_repository("keys", "find_batch");
+        let client = PostgresKeyQuery::new(&self.conn, &self.tenant_id);
+        let keys = client
+            .find_batch(
+                updated_since,
+                after.map(|cursor| (cursor.updated_at, cursor.rule_id, cursor.object_id)),
+                limit,
+            )
+            .await?;
+
+        Ok(keys
+            .into_iter()
+            .map(|key| {
+                let cursor = KeyCursor {
+                    updated_at: key.updated_at,
+                    rule_id: key.rule_id.clone(),
+                    object_id: key.object_id.clone(),
+                };
+                (key.into(), cursor)
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn is_processed(&self, message_id: String) -> Result<bool, DomainError> {
+        let _timer = metrics::time_repository("keys", "is_processed");
+        let client = PostgresProcessedMessageQuery::new(&self.conn, &self.tenant_id);
+        client.exists(message_id).await
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_checkpoint(&self, message_id: String) -> Result<Option<String>, DomainError> {
+        let _timer = metrics::time_repository("keys", "find_checkpoint");
+        let client = PostgresKeySyncCheckpointQuery::new(&self.conn, &self.tenant_id);
+        let checkpoint = client.find_by_id(message_id).await?;
+
+        Ok(checkpoint.map(|checkpoint| checkpoint.last_object_id))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn upsert_by_rule(
         &self,
         rule_id: String,
         keys: Vec<(String, String)>,
     ) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("keys", "upsert_by_rule");
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let tenant_id = self.tenant_id.clone();
         let result = self
             .conn
             .transaction::<_, (), DomainError>(|txn| {
                 Box::pin(async move {
-                    let client = PostgresKeyCommand::new(txn);
+                    let client = PostgresKeyCommand::new(txn, &tenant_id);
                     client.create_many(rule_id, keys).await?;
 
                     Ok(())
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
         Ok(result)
     }
 
-    async fn update_by_rule(
+    #[tracing::instrument(skip_all)]
+    async fn delete_by_objects(
         &self,
         rule_id: String,
-        keys: Vec<(String, String)>,
+        object_ids: Vec<String>,
     ) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("keys", "delete_by_objects");
+        if object_ids.is_empty() {
+            return Ok(());
+        }
+
+        let tenant_id = self.tenant_id.clone();
         let result = self
             .conn
             .transaction::<_, (), DomainError>(|txn| {
                 Box::pin(async move {
-                    let client = PostgresKeyCommand::new(txn);
-                    client.delete_by_rule_id(rule_id.clone()).await?;
-                    client.create_many(rule_id, keys).await?;
+                    let client = PostgresKeyCommand::new(txn, &tenant_id);
+                    client
+                        .delete_by_rule_id_and_object_ids(rule_id, object_ids)
+                        .await?;
 
                     Ok(())
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
         Ok(result)
     }
 
-    async fn delete_by_rule(&self, rule_id: String) -> Result<(), DomainError> {
+    #[tracing::instrument(skip_all)]
+    async fn save_checkpoint(
+        &self,
+        message_id: String,
+        rule_id: String,
+        last_object_id: String,
+    ) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("keys", "save_checkpoint");
+        let tenant_id = self.tenant_id.clone();
         let result = self
             .conn
             .transaction::<_, (), DomainError>(|txn| {
                 Box::pin(async move {
-                    let client = PostgresKeyCommand::new(txn);
+                    let client = PostgresKeySyncCheckpointCommand::new(txn, &tenant_id);
+                    client.save(message_id, rule_id, last_object_id).await?;
+
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn complete_sync(&self, message_id: String) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("keys", "complete_sync");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, (), DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresKeySyncCheckpointCommand::new(txn, &tenant_id);
+                    client.delete(message_id.clone()).await?;
+
+                    let client = PostgresProcessedMessageCommand::new(txn, &tenant_id);
+                    client.create(message_id).await?;
+
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_checkpoint(&self, message_id: String) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("keys", "delete_checkpoint");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, (), DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresKeySyncCheckpointCommand::new(txn, &tenant_id);
+                    client.delete(message_id).await
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_by_rule(&self, rule_id: String, message_id: String) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("keys", "delete_by_rule");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, (), DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresKeyCommand::new(txn, &tenant_id);
                     client.delete_by_rule_id(rule_id).await?;
 
+                    let client = PostgresProcessedMessageCommand::new(txn, &tenant_id);
+                    client.create(message_id).await?;
+
                     Ok(())
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
         Ok(result)
     }
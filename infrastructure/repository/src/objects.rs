@@ -1,40 +1,54 @@
 use database::client::fields::PostgresFieldQuery;
 use database::client::keys::PostgresKeyCommand;
 use database::client::objects::{PostgresObjectCommand, PostgresObjectQuery};
+use database::client::outbox::PostgresOutboxCommand;
 use database::client::rules::PostgresRuleQuery;
-use database::{to_object, to_rule};
+use database::error::transaction_error;
+use database::{to_object, to_partial_object, to_rule};
 use domain::error::DomainError;
-use domain::objects::{Object, ObjectRepository};
+use domain::events::{Event, ObjectChange};
+use domain::objects::{Object, ObjectCursor, ObjectRepository, UpsertResult};
+use domain::tenants::{TenantId, TenantScoped};
 use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::prelude::DateTimeWithTimeZone;
 use sea_orm::{DatabaseConnection, TransactionTrait};
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 pub struct ObjectRepositoryImpl {
     conn: DatabaseConnection,
+    tenant_id: TenantId,
 }
 
 impl ObjectRepositoryImpl {
     pub fn new(conn: DatabaseConnection) -> Self {
-        Self { conn }
+        Self {
+            conn,
+            tenant_id: TenantId::default(),
+        }
+    }
+}
+
+impl TenantScoped for ObjectRepositoryImpl {
+    fn for_tenant(&self, tenant_id: &TenantId) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            tenant_id: tenant_id.clone(),
+        }
     }
 }
 
 #[async_trait]
 impl ObjectRepository for ObjectRepositoryImpl {
+    #[tracing::instrument(skip_all)]
     async fn find_all(&self) -> Result<Vec<Object>, DomainError> {
-        let client = PostgresObjectQuery::new(&self.conn);
-        let objects = client
-            .find_all()
-            .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        let _timer = metrics::time_repository("objects", "find_all");
+        let client = PostgresObjectQuery::new(&self.conn, &self.tenant_id);
+        let objects = client.find_all().await?;
 
-        let client = PostgresFieldQuery::new(&self.conn);
-        let fields = client
-            .find_all()
-            .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
 
         objects
             .into_iter()
@@ -42,39 +56,93 @@ impl ObjectRepository for ObjectRepositoryImpl {
             .collect::<Result<Vec<_>, _>>()
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn count(&self) -> Result<u64, DomainError> {
+        let _timer = metrics::time_repository("objects", "count");
+        let client = PostgresObjectQuery::new(&self.conn, &self.tenant_id);
+        client.count().await
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_page(
+        &self,
+        after: Option<String>,
+        limit: u64,
+    ) -> Result<Vec<Object>, DomainError> {
+        let _timer = metrics::time_repository("objects", "find_page");
+        let client = PostgresObjectQuery::new(&self.conn, &self.tenant_id);
+        let objects = client.find_page(after, limit).await?;
+
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
+
+        objects
+            .into_iter()
+            .map(|object| to_partial_object(object, fields.clone()))
+            .collect::<Result<Vec<_>, _>>()
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_batch(
+        &self,
+        updated_since: Option<DateTimeWithTimeZone>,
+        after: Option<ObjectCursor>,
+        limit: u64,
+    ) -> Result<Vec<(Object, ObjectCursor)>, DomainError> {
+        let _timer = metrics::time_repository("objects", "find_batch");
+        let client = PostgresObjectQuery::new(&self.conn, &self.tenant_id);
+        let objects = client
+            .find_batch(
+                updated_since,
+                after.map(|cursor| (cursor.updated_at, cursor.id)),
+                limit,
+            )
+            .await?;
+
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
+
+        objects
+            .into_iter()
+            .map(|object| {
+                let cursor = ObjectCursor {
+                    updated_at: object.updated_at,
+                    id: object.id.clone(),
+                };
+                Ok((to_object(object, fields.clone())?, cursor))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    }
+
+    #[tracing::instrument(skip_all)]
     async fn get(&self, id: String) -> Result<Option<Object>, DomainError> {
-        let client = PostgresObjectQuery::new(&self.conn);
-        let object = client
-            .find_by_id(id)
-            .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        let _timer = metrics::time_repository("objects", "get");
+        let client = PostgresObjectQuery::new(&self.conn, &self.tenant_id);
+        let object = client.find_by_id(id).await?;
 
-        let client = PostgresFieldQuery::new(&self.conn);
-        let fields = client
-            .find_all()
-            .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
 
         object.map(|v| to_object(v, fields)).transpose()
     }
 
+    #[tracing::instrument(skip_all)]
     async fn create(&self, attributes: HashMap<String, String>) -> Result<Object, DomainError> {
-        let client = PostgresFieldQuery::new(&self.conn);
-        let fields = client
-            .find_all()
-            .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        let _timer = metrics::time_repository("objects", "create");
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
 
         // get exits rules
-        let client = PostgresRuleQuery::new(&self.conn);
+        let client = PostgresRuleQuery::new(&self.conn, &self.tenant_id);
         let rules = client.find_all().await?;
 
+        let tenant_id = self.tenant_id.clone();
         let result = self
             .conn
             .transaction::<_, Object, DomainError>(|txn| {
                 Box::pin(async move {
                     // create object
-                    let client = PostgresObjectCommand::new(txn);
+                    let client = PostgresObjectCommand::new(txn, &tenant_id);
                     let object = client.create(json!(attributes)).await?;
                     let result = to_object(object, fields.clone())?;
 
@@ -90,45 +158,186 @@ impl ObjectRepository for ObjectRepositoryImpl {
 
                     // add new related key
                     if !keys.is_empty() {
-                        let client = PostgresKeyCommand::new(txn);
+                        let client = PostgresKeyCommand::new(txn, &tenant_id);
                         client.create_many2(result.id.to_string(), keys).await?;
                     }
 
+                    let client = PostgresOutboxCommand::new(txn, &tenant_id);
+                    client
+                        .create(Event::ObjectChanged {
+                            object_id: result.id.clone(),
+                            change: ObjectChange::Created,
+                        })
+                        .await?;
+
                     Ok(result)
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create_many(
+        &self,
+        attributes: Vec<HashMap<String, String>>,
+    ) -> Result<Vec<Result<Object, DomainError>>, DomainError> {
+        let _timer = metrics::time_repository("objects", "create_many");
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
+
+        // get existing rules
+        let client = PostgresRuleQuery::new(&self.conn, &self.tenant_id);
+        let rules = client
+            .find_all()
+            .await?
+            .into_iter()
+            .map(|(rule, field)| to_rule(rule, field))
+            .collect::<Vec<_>>();
+
+        // validate objects and generate keys before touching the database
+        let mut objects = Vec::new();
+        let mut keys = Vec::new();
+        let mut events = Vec::new();
+        let results = attributes
+            .into_iter()
+            .map(|attributes| {
+                let object = PostgresObjectCommand::build(&self.tenant_id, json!(attributes));
+                let result = to_object(object.clone(), fields.clone())?;
+                let generated = rules
+                    .iter()
+                    .map(|rule| {
+                        let key = rule.generate_key(result.clone())?;
+                        Ok((rule.id.clone(), result.id.clone(), key))
+                    })
+                    .collect::<Result<Vec<_>, DomainError>>()?;
+
+                events.push(Event::ObjectChanged {
+                    object_id: object.id.clone(),
+                    change: ObjectChange::Created,
+                });
+                objects.push(object);
+                keys.extend(generated);
+                Ok(result)
+            })
+            .collect::<Vec<_>>();
+
+        let tenant_id = self.tenant_id.clone();
+        self.conn
+            .transaction::<_, (), DomainError>(|txn| {
+                Box::pin(async move {
+                    // create objects
+                    let client = PostgresObjectCommand::new(txn, &tenant_id);
+                    client.create_in_chunks(objects).await?;
+
+                    // add new related keys
+                    let client = PostgresKeyCommand::new(txn, &tenant_id);
+                    client.create_in_chunks(keys).await?;
+
+                    let client = PostgresOutboxCommand::new(txn, &tenant_id);
+                    client.create_in_chunks(events).await?;
+
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(results)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn upsert(
+        &self,
+        source: String,
+        external_id: String,
+        attributes: HashMap<String, String>,
+    ) -> Result<(Object, UpsertResult), DomainError> {
+        let _timer = metrics::time_repository("objects", "upsert");
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
+
+        // get existing rules
+        let client = PostgresRuleQuery::new(&self.conn, &self.tenant_id);
+        let rules = client.find_all().await?;
+
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, (Object, UpsertResult), DomainError>(|txn| {
+                Box::pin(async move {
+                    let attributes = json!(attributes);
+                    let client = PostgresObjectCommand::new(txn, &tenant_id);
+                    let (object, upsert_result) =
+                        client.upsert(source, external_id, attributes).await?;
+                    let result = to_object(object, fields.clone())?;
+                    if upsert_result == UpsertResult::Unchanged {
+                        return Ok((result, upsert_result));
+                    }
+
+                    // generate keys
+                    let keys = rules
+                        .into_iter()
+                        .map(|rule| {
+                            let rule = to_rule(rule.0, rule.1);
+                            let key = rule.generate_key(result.clone())?;
+                            Ok((rule.id, key))
+                        })
+                        .collect::<Result<Vec<_>, DomainError>>()?;
+
+                    // add or replace related key
+                    if !keys.is_empty() {
+                        let client = PostgresKeyCommand::new(txn, &tenant_id);
+                        client.upsert_by_object(result.id.to_string(), keys).await?;
+                    }
+
+                    let client = PostgresOutboxCommand::new(txn, &tenant_id);
+                    client
+                        .create(Event::ObjectChanged {
+                            object_id: result.id.clone(),
+                            change: match upsert_result {
+                                UpsertResult::Created => ObjectChange::Created,
+                                _ => ObjectChange::Updated,
+                            },
+                        })
+                        .await?;
+
+                    Ok((result, upsert_result))
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
 
         Ok(result)
     }
 
+    #[tracing::instrument(skip_all)]
     async fn update(
         &self,
         id: String,
         attributes: HashMap<String, String>,
     ) -> Result<Object, DomainError> {
-        let client = PostgresFieldQuery::new(&self.conn);
-        let fields = client
-            .find_all()
-            .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+        let _timer = metrics::time_repository("objects", "update");
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
 
         // get exits rules
-        let client = PostgresRuleQuery::new(&self.conn);
+        let client = PostgresRuleQuery::new(&self.conn, &self.tenant_id);
         let rules = client.find_all().await?;
 
+        let tenant_id = self.tenant_id.clone();
         let result = self
             .conn
             .transaction::<_, Object, DomainError>(|txn| {
                 Box::pin(async move {
-                    let client = PostgresObjectCommand::new(txn);
+                    let client = PostgresObjectCommand::new(txn, &tenant_id);
                     let object = client.update(id, json!(attributes)).await?;
                     let result = to_object(object, fields.clone())?;
 
                     // generate keys
                     let keys = rules
-                        .clone()
                         .into_iter()
                         .map(|rule| {
                             let rule = to_rule(rule.0, rule.1);
@@ -137,42 +346,124 @@ impl ObjectRepository for ObjectRepositoryImpl {
                         })
                         .collect::<Result<Vec<_>, DomainError>>()?;
 
+                    // replace related key of this object only
                     if !keys.is_empty() {
-                        let client = PostgresKeyCommand::new(txn);
-                        // remove related key
-                        for (rule, _) in rules {
-                            client.delete_by_rule_id(rule.id).await?;
-                        }
-                        // add new related key
-                        client.create_many2(result.id.to_string(), keys).await?;
+                        let client = PostgresKeyCommand::new(txn, &tenant_id);
+                        client.upsert_by_object(result.id.to_string(), keys).await?;
                     }
 
+                    let client = PostgresOutboxCommand::new(txn, &tenant_id);
+                    client
+                        .create(Event::ObjectChanged {
+                            object_id: result.id.clone(),
+                            change: ObjectChange::Updated,
+                        })
+                        .await?;
+
                     Ok(result)
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
         Ok(result)
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn patch(
+        &self,
+        id: String,
+        set: HashMap<String, String>,
+        unset: Vec<String>,
+    ) -> Result<Object, DomainError> {
+        let _timer = metrics::time_repository("objects", "patch");
+        let client = PostgresFieldQuery::new(&self.conn, &self.tenant_id);
+        let fields = client.find_all().await?;
+
+        // only rules of touched fields need new keys
+        let touched = set
+            .keys()
+            .chain(unset.iter())
+            .cloned()
+            .collect::<HashSet<_>>();
+        let client = PostgresRuleQuery::new(&self.conn, &self.tenant_id);
+        let rules = client
+            .find_all()
+            .await?
+            .into_iter()
+            .map(|(rule, field)| to_rule(rule, field))
+            .filter(|rule| touched.contains(&rule.field.data_label))
+            .collect::<Vec<_>>();
+
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, Object, DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresObjectCommand::new(txn, &tenant_id);
+                    let object = client.merge(id, json!(set), unset).await?;
+                    let result = to_object(object, fields)?;
+
+                    // generate keys
+                    let keys = rules
+                        .into_iter()
+                        .map(|rule| {
+                            let key = rule.generate_key(result.clone())?;
+                            Ok((rule.id, key))
+                        })
+                        .collect::<Result<Vec<_>, DomainError>>()?;
+
+                    // replace related key of this object only
+                    if !keys.is_empty() {
+                        let client = PostgresKeyCommand::new(txn, &tenant_id);
+                        client.upsert_by_object(result.id.to_string(), keys).await?;
+                    }
+
+                    let client = PostgresOutboxCommand::new(txn, &tenant_id);
+                    client
+                        .create(Event::ObjectChanged {
+                            object_id: result.id.clone(),
+                            change: ObjectChange::Updated,
+                        })
+                        .await?;
+
+                    Ok(result)
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
     async fn delete(&self, id: String) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("objects", "delete");
+        let tenant_id = self.tenant_id.clone();
         let _ = self
             .conn
             .transaction::<_, (), DomainError>(|txn| {
                 Box::pin(async move {
-                    let client = PostgresObjectCommand::new(txn);
+                    let client = PostgresObjectCommand::new(txn, &tenant_id);
                     client.delete(id.clone()).await?;
 
                     // delete related keys
-                    let client = PostgresKeyCommand::new(txn);
-                    client.delete_by_object_id(id).await?;
+                    let client = PostgresKeyCommand::new(txn, &tenant_id);
+                    client.delete_by_object_id(id.clone()).await?;
+
+                    let client = PostgresOutboxCommand::new(txn, &tenant_id);
+                    client
+                        .create(Event::ObjectChanged {
+                            object_id: id,
+                            change: ObjectChange::Deleted,
+                        })
+                        .await?;
 
                     Ok(())
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
         Ok(())
     }
@@ -0,0 +1,68 @@
+use database::client::outbox::PostgresOutboxRelayCommand;
+use database::error::transaction_error;
+use database::to_outbox_event;
+use domain::error::DomainError;
+use domain::events::{EventPublisher, OutboxRepository};
+use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+
+#[derive(Debug, Clone)]
+pub struct OutboxRepositoryImpl {
+    conn: DatabaseConnection,
+}
+
+impl OutboxRepositoryImpl {
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for OutboxRepositoryImpl {
+    #[tracing::instrument(skip_all)]
+    async fn relay<P: EventPublisher>(
+        &self,
+        publisher: &P,
+        limit: u64,
+    ) -> Result<usize, DomainError> {
+        let _timer = metrics::time_repository("outbox", "relay");
+        let publisher = publisher.clone();
+        let (published, error) = self
+            .conn
+            .transaction::<_, (usize, Option<DomainError>), DomainError>(|txn| {
+                Box::pin(async move {
+                    // events stay locked until they are marked, so relays never publish concurrently
+                    let client = PostgresOutboxRelayCommand::new(txn);
+                    let events = client.find_unpublished(limit).await?;
+
+                    let mut sequences = Vec::new();
+                    let mut error = None;
+                    for event in events {
+                        let sequence = event.sequence;
+                        let result = match to_outbox_event(event) {
+                            Ok(event) => publisher.publish(event).await,
+                            Err(e) => Err(e),
+                        };
+                        // stop at the first failure so that later events are not published ahead of it
+                        if let Err(e) = result {
+                            error = Some(e);
+                            break;
+                        }
+                        sequences.push(sequence);
+                    }
+
+                    let published = sequences.len();
+                    client.mark_published(sequences).await?;
+
+                    Ok((published, error))
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(published),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use database::client::processed_messages::PostgresProcessedMessageRetentionCommand;
+use database::error::transaction_error;
+use domain::error::DomainError;
+use domain::keys::ProcessedMessageRepository;
+use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+
+#[derive(Debug, Clone)]
+pub struct ProcessedMessageRepositoryImpl {
+    conn: DatabaseConnection,
+}
+
+impl ProcessedMessageRepositoryImpl {
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self { conn }
+    }
+}
+
+#[async_trait]
+impl ProcessedMessageRepository for ProcessedMessageRepositoryImpl {
+    #[tracing::instrument(skip_all)]
+    async fn delete_before(&self, before: DateTimeWithTimeZone) -> Result<u64, DomainError> {
+        let _timer = metrics::time_repository("processed_messages", "delete_before");
+        let result = self
+            .conn
+            .transaction::<_, u64, DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresProcessedMessageRetentionCommand::new(txn);
+                    client.delete_before(before).await
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+}
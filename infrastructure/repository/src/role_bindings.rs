@@ -0,0 +1,83 @@
+use database::client::role_bindings::{PostgresRoleBindingCommand, PostgresRoleBindingQuery};
+use database::error::transaction_error;
+use domain::auth::{Role, RoleBinding, RoleBindingRepository};
+use domain::error::DomainError;
+use domain::tenants::{TenantId, TenantScoped};
+use sea_orm::prelude::async_trait::async_trait;
+use sea_orm::{DatabaseConnection, TransactionTrait};
+
+#[derive(Debug, Clone)]
+pub struct RoleBindingRepositoryImpl {
+    conn: DatabaseConnection,
+    tenant_id: TenantId,
+}
+
+impl RoleBindingRepositoryImpl {
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self {
+            conn,
+            tenant_id: TenantId::default(),
+        }
+    }
+}
+
+impl TenantScoped for RoleBindingRepositoryImpl {
+    fn for_tenant(&self, tenant_id: &TenantId) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            tenant_id: tenant_id.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl RoleBindingRepository for RoleBindingRepositoryImpl {
+    #[tracing::instrument(skip_all)]
+    async fn find_all(&self, subject: Option<String>) -> Result<Vec<RoleBinding>, DomainError> {
+        let _timer = metrics::time_repository("role_bindings", "find_all");
+        let client = PostgresRoleBindingQuery::new(&self.conn, &self.tenant_id);
+        let role_bindings = client.find_all(subject).await?;
+
+        role_bindings.into_iter().map(TryInto::try_into).collect()
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create(&self, subject: String, role: Role) -> Result<RoleBinding, DomainError> {
+        let _timer = metrics::time_repository("role_bindings", "create");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
+            .conn
+            .transaction::<_, RoleBinding, DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresRoleBindingCommand::new(txn, &tenant_id);
+                    let result = client.create(subject, role).await?;
+
+                    result.try_into()
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete(&self, id: String) -> Result<(), DomainError> {
+        let _timer = metrics::time_repository("role_bindings", "delete");
+        let tenant_id = self.tenant_id.clone();
+        let _ = self
+            .conn
+            .transaction::<_, (), DomainError>(|txn| {
+                Box::pin(async move {
+                    let client = PostgresRoleBindingCommand::new(txn, &tenant_id);
+                    client.delete(id).await?;
+
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(transaction_error)?;
+
+        Ok(())
+    }
+}
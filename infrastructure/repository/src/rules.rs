@@ -1,28 +1,48 @@
-use database::client::fields::PostgresFieldQuery;
-use database::client::keys::PostgresKeyCommand;
-use database::client::objects::PostgresObjectQuery;
+use database::client::jobs::PostgresJobCommand;
+use database::client::outbox::PostgresOutboxCommand;
 use database::client::rules::{PostgresRuleCommand, PostgresRuleQuery};
-use database::{to_object, to_rule};
+use database::error::transaction_error;
+use database::to_rule;
 use domain::error::DomainError;
+use domain::events::Event;
+use domain::jobs::{JobType, KeySyncJob};
 use domain::rules::{GenerationRule, Rule, RuleRepository};
+use domain::tenants::{TenantId, TenantScoped};
 use sea_orm::prelude::async_trait::async_trait;
 use sea_orm::{DatabaseConnection, TransactionTrait};
 
 #[derive(Debug, Clone)]
 pub struct RuleRepositoryImpl {
     conn: DatabaseConnection,
+    tenant_id: TenantId,
 }
 
 impl RuleRepositoryImpl {
     pub fn new(conn: DatabaseConnection) -> Self {
-        Self { conn }
+        Self {
+            conn,
+            tenant_id: TenantId::default(),
+        }
     }
 }
 
+impl TenantScoped for RuleRepositoryImpl {
+    fn for_tenant(&self, tenant_id: &TenantId) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            tenant_id: tenant_id.clone(),
+        }
+    }
+}
+
+// keys of a rule are generated by the worker from the job written to the outbox,
+// see `subscriber::keys::KeysHandler`
 #[async_trait]
 impl RuleRepository for RuleRepositoryImpl {
+    #[tracing::instrument(skip_all)]
     async fn find_all(&self) -> Result<Vec<Rule>, DomainError> {
-        let client = PostgresRuleQuery::new(&self.conn);
+        let _timer = metrics::time_repository("rules", "find_all");
+        let client = PostgresRuleQuery::new(&self.conn, &self.tenant_id);
         let response = client.find_all().await?;
 
         Ok(response
@@ -31,141 +51,127 @@ impl RuleRepository for RuleRepositoryImpl {
             .collect())
     }
 
+    #[tracing::instrument(skip_all)]
     async fn get(&self, id: String) -> Result<Option<Rule>, DomainError> {
-        let client = PostgresRuleQuery::new(&self.conn);
+        let _timer = metrics::time_repository("rules", "get");
+        let client = PostgresRuleQuery::new(&self.conn, &self.tenant_id);
         let response = client.find_by_id(id).await?;
 
         Ok(response.map(|(rule, field)| to_rule(rule, field)))
     }
 
+    #[tracing::instrument(skip_all)]
     async fn create(
         &self,
         field_id: String,
         rule_type: GenerationRule,
-    ) -> Result<Rule, DomainError> {
-        let client = PostgresFieldQuery::new(&self.conn);
-        let fields = client
-            .find_all()
-            .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
-
-        // get exits objects
-        let client = PostgresObjectQuery::new(&self.conn);
-        let objects = client.find_all().await?;
-        let objects = objects
-            .into_iter()
-            .map(|object| to_object(object, fields.clone()))
-            .collect::<Result<Vec<_>, _>>()?;
-
+    ) -> Result<(Rule, String), DomainError> {
+        let _timer = metrics::time_repository("rules", "create");
+        let tenant_id = self.tenant_id.clone();
         let result = self
             .conn
-            .transaction::<_, Rule, DomainError>(|txn| {
+            .transaction::<_, (Rule, String), DomainError>(|txn| {
                 Box::pin(async move {
                     // add new rule
-                    let client = PostgresRuleCommand::new(txn);
+                    let client = PostgresRuleCommand::new(txn, &tenant_id);
                     let result = client.create(field_id, rule_type).await?;
                     let rule = to_rule(result.0, result.1);
 
-                    // TODO: 件数が多い場合時間がかかるため Pub/Sub を使うようにしたい
-                    // generate keys
-                    let keys = objects
-                        .into_iter()
-                        .map(|object| {
-                            let key = rule.generate_key(object.clone())?;
-                            Ok((object.id, key))
-                        })
-                        .collect::<Result<Vec<_>, DomainError>>()?;
-
-                    // add new related key
-                    if !keys.is_empty() {
-                        let client = PostgresKeyCommand::new(txn);
-                        client.create_many(rule.id.clone(), keys).await?;
-                    }
-
-                    Ok(rule)
+                    // enqueue key generation
+                    let client = PostgresOutboxCommand::new(txn, &tenant_id);
+                    let event = client
+                        .create(Event::KeySync(KeySyncJob::Create {
+                            rule_id: rule.id.clone(),
+                            rule_version: rule.version,
+                        }))
+                        .await?;
+                    let client = PostgresJobCommand::new(txn, &tenant_id);
+                    client
+                        .create(event.event_id.clone(), JobType::CreateKeys, rule.id.clone())
+                        .await?;
+
+                    Ok((rule, event.event_id))
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
         Ok(result)
     }
 
+    #[tracing::instrument(skip_all)]
     async fn update(
         &self,
         id: String,
         field_id: String,
         rule_type: GenerationRule,
-    ) -> Result<Rule, DomainError> {
-        let client = PostgresFieldQuery::new(&self.conn);
-        let fields = client
-            .find_all()
-            .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
-
-        // get exits objects
-        let client = PostgresObjectQuery::new(&self.conn);
-        let objects = client.find_all().await?;
-        let objects = objects
-            .into_iter()
-            .map(|object| to_object(object, fields.clone()))
-            .collect::<Result<Vec<_>, _>>()?;
-
+    ) -> Result<(Rule, String), DomainError> {
+        let _timer = metrics::time_repository("rules", "update");
+        let tenant_id = self.tenant_id.clone();
         let result = self
             .conn
-            .transaction::<_, Rule, DomainError>(|txn| {
+            .transaction::<_, (Rule, String), DomainError>(|txn| {
                 Box::pin(async move {
                     // update exists rule
-                    let client = PostgresRuleCommand::new(txn);
-                    let result = client.update(id.clone(), field_id, rule_type).await?;
+                    let client = PostgresRuleCommand::new(txn, &tenant_id);
+                    let result = client.update(id, field_id, rule_type).await?;
                     let rule = to_rule(result.0, result.1);
 
-                    // TODO: 件数が多い場合時間がかかるため Pub/Sub を使うようにしたい
-                    // generate keys
-                    let keys = objects
-                        .into_iter()
-                        .map(|object| {
-                            let key = rule.generate_key(object.clone())?;
-                            Ok((object.id, key))
-                        })
-                        .collect::<Result<Vec<_>, DomainError>>()?;
-
-                    if !keys.is_empty() {
-                        let client = PostgresKeyCommand::new(txn);
-                        // remove related key
-                        client.delete_by_rule_id(id).await?;
-                        // add new related key
-                        client.create_many(rule.id.clone(), keys).await?;
-                    }
-
-                    Ok(rule)
+                    // enqueue key regeneration
+                    let client = PostgresOutboxCommand::new(txn, &tenant_id);
+                    let event = client
+                        .create(Event::KeySync(KeySyncJob::Update {
+                            rule_id: rule.id.clone(),
+                            rule_version: rule.version,
+                        }))
+                        .await?;
+                    let client = PostgresJobCommand::new(txn, &tenant_id);
+                    client
+                        .create(event.event_id.clone(), JobType::UpdateKeys, rule.id.clone())
+                        .await?;
+
+                    Ok((rule, event.event_id))
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
         Ok(result)
     }
 
-    async fn delete(&self, id: String) -> Result<(), DomainError> {
-        let _ = self
+    #[tracing::instrument(skip_all)]
+    async fn delete(&self, id: String) -> Result<String, DomainError> {
+        let _timer = metrics::time_repository("rules", "delete");
+        let tenant_id = self.tenant_id.clone();
+        let result = self
             .conn
-            .transaction::<_, (), DomainError>(|txn| {
+            .transaction::<_, String, DomainError>(|txn| {
                 Box::pin(async move {
-                    let client = PostgresRuleCommand::new(txn);
-                    client.delete(id.clone()).await?;
-
-                    // TODO: 件数が多い場合時間がかかるため Pub/Sub を使うようにしたい
-                    // delete related keys
-                    let client = PostgresKeyCommand::new(txn);
-                    client.delete_by_rule_id(id).await?;
-
-                    Ok(())
+                    let client = PostgresRuleCommand::new(txn, &tenant_id);
+                    let rule = client
+                        .delete(id.clone())
+                        .await?
+                        .ok_or(DomainError::NotFound(id.clone()))?;
+
+                    // enqueue key deletion
+                    let client = PostgresOutboxCommand::new(txn, &tenant_id);
+                    let event = client
+                        .create(Event::KeySync(KeySyncJob::Delete {
+                            rule_id: id.clone(),
+                            rule_version: rule.version,
+                        }))
+                        .await?;
+                    let client = PostgresJobCommand::new(txn, &tenant_id);
+                    client
+                        .create(event.event_id.clone(), JobType::DeleteKeys, id)
+                        .await?;
+
+                    Ok(event.event_id)
                 })
             })
             .await
-            .map_err(|e| DomainError::Unexpected(e.to_string()))?;
+            .map_err(transaction_error)?;
 
-        Ok(())
+        Ok(result)
     }
 }
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::Receiver;
+use tokio_util::sync::CancellationToken;
+
+pub mod memory;
+pub mod postgres;
+pub mod pubsub;
+
+/// Number of received messages buffered ahead of the consumer.
+const BUFFER_SIZE: usize = 64;
+/// Attribute holding the time a message was published at, in milliseconds since the Unix epoch.
+pub const PUBLISHED_AT: &str = "published_at";
+/// Attribute holding the tenant of the event.
+pub const TENANT_ID: &str = "tenant_id";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Message {
+    pub data: Vec<u8>,
+    pub attributes: HashMap<String, String>,
+    /// Messages with the same non-empty key are delivered in the order they were published.
+    pub ordering_key: String,
+}
+
+/// Returns the time the message was published at, if the publisher recorded it.
+pub fn published_at(message: &Message) -> Option<SystemTime> {
+    let millis = message.attributes.get(PUBLISHED_AT)?.parse().ok()?;
+
+    Some(UNIX_EPOCH + Duration::from_millis(millis))
+}
+
+/// A received message, settled with `Broker::ack` or `Broker::nack`.
+pub trait Delivery: Send + Sync + 'static {
+    fn message(&self) -> &Message;
+    /// Number of times the message has been delivered, starting from 1.
+    fn attempt(&self) -> u32;
+}
+
+#[async_trait]
+pub trait Broker: Clone + Send + Sync + 'static {
+    type Delivery: Delivery;
+
+    async fn publish(&self, topic: &str, message: Message) -> Result<()>;
+    /// Starts receiving the messages published to `topic` through `subscription`.
+    /// No more messages are received once `cancel` is cancelled, and the receiver is then closed.
+    async fn subscribe(
+        &self,
+        topic: &str,
+        subscription: &str,
+        cancel: CancellationToken,
+    ) -> Result<Receiver<Self::Delivery>>;
+    async fn ack(&self, delivery: Self::Delivery) -> Result<()>;
+    /// Hands the message back to the broker so that it is delivered again after `delay`.
+    async fn nack(&self, delivery: Self::Delivery, delay: Duration) -> Result<()>;
+}
@@ -0,0 +1,124 @@
+use super::{Broker, Delivery, Message, BUFFER_SIZE};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver, UnboundedReceiver, UnboundedSender};
+use tokio_util::sync::CancellationToken;
+
+/// In-process broker for tests. Every topic has a single subscription and ordering keys are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryBroker {
+    topics: Arc<Mutex<HashMap<String, Topic>>>,
+}
+
+/// A message and the number of times it has been delivered so far.
+type Entry = (Message, u32);
+
+#[derive(Debug)]
+struct Topic {
+    sender: UnboundedSender<Entry>,
+    receiver: Option<UnboundedReceiver<Entry>>,
+}
+
+#[derive(Debug)]
+pub struct InMemoryDelivery {
+    message: Message,
+    attempt: u32,
+    requeue: UnboundedSender<Entry>,
+}
+
+impl Delivery for InMemoryDelivery {
+    fn message(&self) -> &Message {
+        &self.message
+    }
+
+    fn attempt(&self) -> u32 {
+        self.attempt
+    }
+}
+
+impl InMemoryBroker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn sender(&self, topic: &str) -> UnboundedSender<Entry> {
+        let mut topics = self.topics.lock().unwrap();
+        topics
+            .entry(topic.to_string())
+            .or_insert_with(|| {
+                let (sender, receiver) = mpsc::unbounded_channel();
+                Topic {
+                    sender,
+                    receiver: Some(receiver),
+                }
+            })
+            .sender
+            .clone()
+    }
+}
+
+#[async_trait]
+impl Broker for InMemoryBroker {
+    type Delivery = InMemoryDelivery;
+
+    async fn publish(&self, topic: &str, message: Message) -> Result<()> {
+        self.sender(topic)
+            .send((message, 0))
+            .map_err(|_| anyhow!("topic closed: [{}]", topic))
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        _subscription: &str,
+        cancel: CancellationToken,
+    ) -> Result<Receiver<Self::Delivery>> {
+        let requeue = self.sender(topic);
+        let mut messages = self
+            .topics
+            .lock()
+            .unwrap()
+            .get_mut(topic)
+            .and_then(|topic| topic.receiver.take())
+            .ok_or(anyhow!("already subscribed: [{}]", topic))?;
+
+        let (sender, receiver) = mpsc::channel(BUFFER_SIZE);
+        tokio::spawn(async move {
+            loop {
+                let entry = tokio::select! {
+                    entry = messages.recv() => entry,
+                    _ = cancel.cancelled() => None,
+                };
+                let Some((message, attempts)) = entry else {
+                    break;
+                };
+                let delivery = InMemoryDelivery {
+                    message,
+                    attempt: attempts + 1,
+                    requeue: requeue.clone(),
+                };
+                if sender.send(delivery).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    async fn ack(&self, _delivery: Self::Delivery) -> Result<()> {
+        Ok(())
+    }
+
+    async fn nack(&self, delivery: Self::Delivery, delay: Duration) -> Result<()> {
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _ = delivery.requeue.send((delivery.message, delivery.attempt));
+        });
+
+        Ok(())
+    }
+}
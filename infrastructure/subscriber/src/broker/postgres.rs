@@ -0,0 +1,218 @@
+use super::{Broker, Delivery, Message, BUFFER_SIZE};
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::{PgListener, PgPool};
+use sqlx::types::Json;
+use sqlx::Row;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio_util::sync::CancellationToken;
+
+/// Channel notified with the topic of every published message.
+const CHANNEL: &str = "broker_messages";
+/// How long a received message stays invisible to other consumers before it is delivered again.
+const LEASE: Duration = Duration::from_secs(60);
+/// Upper bound of the wait for a notification, so that expired leases are picked up.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Broker backed by the `broker_messages` table, for running without Pub/Sub.
+/// Consumers are woken up with `LISTEN/NOTIFY` and the table is polled as a fallback.
+/// Every topic has a single subscription, shared by all consumers of the topic.
+#[derive(Clone, Debug)]
+pub struct PostgresBroker {
+    pool: PgPool,
+}
+
+#[derive(Debug)]
+pub struct PostgresDelivery {
+    id: i64,
+    message: Message,
+    attempt: u32,
+}
+
+impl Delivery for PostgresDelivery {
+    fn message(&self) -> &Message {
+        &self.message
+    }
+
+    fn attempt(&self) -> u32 {
+        self.attempt
+    }
+}
+
+impl PostgresBroker {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        Ok(Self::new(PgPool::connect(database_url).await?))
+    }
+
+    /// Leases the oldest visible messages of a topic, skipping keys that have an earlier message pending.
+    async fn receive(&self, topic: &str, limit: i64) -> Result<Vec<PostgresDelivery>> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE "broker_messages"
+            SET "visible_at" = now() + make_interval(secs => $3), "attempts" = "attempts" + 1
+            WHERE "id" IN (
+                SELECT "id" FROM "broker_messages" AS m
+                WHERE m."topic" = $1
+                  AND m."visible_at" <= now()
+                  AND (m."ordering_key" = '' OR NOT EXISTS (
+                      SELECT 1 FROM "broker_messages" AS p
+                      WHERE p."topic" = m."topic"
+                        AND p."ordering_key" = m."ordering_key"
+                        AND p."id" < m."id"
+                  ))
+                ORDER BY m."id"
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING "id", "ordering_key", "data", "attributes", "attempts"
+            "#,
+        )
+        .bind(topic)
+        .bind(limit)
+        .bind(LEASE.as_secs_f64())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut deliveries = rows
+            .into_iter()
+            .map(|row| {
+                let attributes: Json<HashMap<String, String>> = row.try_get("attributes")?;
+                let attempts: i32 = row.try_get("attempts")?;
+                Ok(PostgresDelivery {
+                    id: row.try_get("id")?,
+                    attempt: attempts as u32,
+                    message: Message {
+                        data: row.try_get("data")?,
+                        attributes: attributes.0,
+                        ordering_key: row.try_get("ordering_key")?,
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>, sqlx::Error>>()?;
+        // RETURNING does not keep the order of the subquery
+        deliveries.sort_by_key(|delivery| delivery.id);
+
+        Ok(deliveries)
+    }
+
+    async fn forward(
+        self,
+        topic: String,
+        sender: Sender<PostgresDelivery>,
+        cancel: CancellationToken,
+    ) -> Result<()> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(CHANNEL).await?;
+
+        while !cancel.is_cancelled() {
+            // lease no more than the consumer can take, the lease runs while a message is buffered
+            let limit = sender.capacity().max(1) as i64;
+            let deliveries = self.receive(&topic, limit).await?;
+            if deliveries.is_empty() {
+                // a notification of any topic triggers a new receive
+                tokio::select! {
+                    _ = tokio::time::timeout(POLL_INTERVAL, listener.recv()) => {}
+                    _ = cancel.cancelled() => {}
+                }
+                continue;
+            }
+            for delivery in deliveries {
+                if sender.send(delivery).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Broker for PostgresBroker {
+    type Delivery = PostgresDelivery;
+
+    async fn publish(&self, topic: &str, message: Message) -> Result<()> {
+        sqlx::query(
+            r#"
+            WITH m AS (
+                INSERT INTO "broker_messages" ("topic", "ordering_key", "data", "attributes")
+                VALUES ($1, $2, $3, $4)
+                RETURNING "topic"
+            )
+            SELECT pg_notify($5, m."topic") FROM m
+            "#,
+        )
+        .bind(topic)
+        .bind(message.ordering_key)
+        .bind(message.data)
+        .bind(Json(message.attributes))
+        .bind(CHANNEL)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        _subscription: &str,
+        cancel: CancellationToken,
+    ) -> Result<Receiver<Self::Delivery>> {
+        let (sender, receiver) = mpsc::channel(BUFFER_SIZE);
+        let broker = self.clone();
+        let topic = topic.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = broker.forward(topic, sender, cancel).await {
+                tracing::error!(error = %e, "failed to receive messages");
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    async fn ack(&self, delivery: Self::Delivery) -> Result<()> {
+        // the next message of the same ordering key becomes receivable
+        sqlx::query(
+            r#"
+            WITH m AS (
+                DELETE FROM "broker_messages" WHERE "id" = $1
+                RETURNING "topic"
+            )
+            SELECT pg_notify($2, m."topic") FROM m
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(CHANNEL)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn nack(&self, delivery: Self::Delivery, delay: Duration) -> Result<()> {
+        sqlx::query(
+            r#"
+            WITH m AS (
+                UPDATE "broker_messages" SET "visible_at" = now() + make_interval(secs => $3)
+                WHERE "id" = $1
+                RETURNING "topic"
+            )
+            SELECT pg_notify($2, m."topic") FROM m
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(CHANNEL)
+        .bind(delay.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
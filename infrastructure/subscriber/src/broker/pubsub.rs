@@ -0,0 +1,190 @@
+use super::{Broker, Delivery, Message, BUFFER_SIZE};
+use anyhow::Result;
+use async_trait::async_trait;
+use google_cloud_default::WithAuthExt;
+use google_cloud_gax::conn::Environment;
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use google_cloud_pubsub::client::{Client, ClientConfig};
+use google_cloud_pubsub::publisher::Publisher;
+use google_cloud_pubsub::subscriber::ReceivedMessage;
+use google_cloud_pubsub::subscription::SubscriptionConfig;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, Receiver};
+use tokio_util::sync::CancellationToken;
+
+/// How long the delivery count of a message is kept after its last delivery. Messages acked by
+/// another worker, or whose ack failed, are never settled here and would otherwise stay forever.
+const ATTEMPTS_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone)]
+pub struct PubSubBroker {
+    client: Client,
+    publishers: Arc<Mutex<HashMap<String, Publisher>>>,
+    // delivery counts of unsettled messages, the client does not expose the ones of Pub/Sub
+    attempts: Arc<Mutex<Attempts>>,
+}
+
+/// Delivery counts and last delivery times of messages, by message id.
+#[derive(Default)]
+struct Attempts(HashMap<String, (u32, Instant)>);
+
+impl Attempts {
+    /// Counts a delivery of `message_id`, forgetting the messages not delivered for `ATTEMPTS_TTL`.
+    fn next(&mut self, message_id: &str) -> u32 {
+        let now = Instant::now();
+        self.0
+            .retain(|_, (_, delivered_at)| now.duration_since(*delivered_at) < ATTEMPTS_TTL);
+        let (attempt, delivered_at) = self.0.entry(message_id.to_string()).or_insert((0, now));
+        *attempt += 1;
+        *delivered_at = now;
+
+        *attempt
+    }
+
+    fn remove(&mut self, message_id: &str) {
+        self.0.remove(message_id);
+    }
+}
+
+pub struct PubSubDelivery {
+    message: Message,
+    attempt: u32,
+    received: ReceivedMessage,
+}
+
+impl Delivery for PubSubDelivery {
+    fn message(&self) -> &Message {
+        &self.message
+    }
+
+    fn attempt(&self) -> u32 {
+        self.attempt
+    }
+}
+
+impl PubSubBroker {
+    /// Connects to `emulator_host` when it is set, to Google Cloud otherwise. The project defaults
+    /// to the one of the credentials.
+    pub async fn new(project_id: Option<String>, emulator_host: Option<String>) -> Result<Self> {
+        let mut client_config = ClientConfig::default();
+        if let Some(emulator_host) = emulator_host {
+            client_config.environment = Environment::Emulator(emulator_host);
+        }
+        let mut client_config = client_config.with_auth().await?;
+        if project_id.is_some() {
+            client_config.project_id = project_id;
+        }
+        let client = Client::new(client_config).await?;
+
+        Ok(Self {
+            client,
+            publishers: Arc::new(Mutex::new(HashMap::new())),
+            attempts: Arc::new(Mutex::new(Attempts::default())),
+        })
+    }
+
+    fn publisher(&self, topic: &str) -> Publisher {
+        let mut publishers = self.publishers.lock().unwrap();
+        publishers
+            .entry(topic.to_string())
+            .or_insert_with(|| self.client.topic(topic).new_publisher(None))
+            .clone()
+    }
+}
+
+#[async_trait]
+impl Broker for PubSubBroker {
+    type Delivery = PubSubDelivery;
+
+    async fn publish(&self, topic: &str, message: Message) -> Result<()> {
+        let message = PubsubMessage {
+            data: message.data,
+            attributes: message.attributes,
+            ordering_key: message.ordering_key,
+            ..Default::default()
+        };
+        let awaiter = self.publisher(topic).publish(message).await;
+        awaiter.get().await?;
+
+        Ok(())
+    }
+
+    async fn subscribe(
+        &self,
+        topic: &str,
+        subscription: &str,
+        cancel: CancellationToken,
+    ) -> Result<Receiver<Self::Delivery>> {
+        let subscription = self.client.subscription(subscription);
+        if !subscription.exists(None).await? {
+            let topic = self.client.topic(topic);
+            let config = SubscriptionConfig {
+                enable_message_ordering: true,
+                ..Default::default()
+            };
+            subscription
+                .create(topic.fully_qualified_name(), config, None)
+                .await?;
+        }
+
+        let (sender, receiver) = mpsc::channel(BUFFER_SIZE);
+        let attempts = self.attempts.clone();
+        tokio::spawn(async move {
+            let result = subscription
+                .receive(
+                    move |received, _cancel| {
+                        let sender = sender.clone();
+                        let attempts = attempts.clone();
+                        async move {
+                            let message = Message {
+                                data: received.message.data.clone(),
+                                attributes: received.message.attributes.clone(),
+                                ordering_key: received.message.ordering_key.clone(),
+                            };
+                            let attempt =
+                                attempts.lock().unwrap().next(&received.message.message_id);
+                            let delivery = PubSubDelivery {
+                                message,
+                                attempt,
+                                received,
+                            };
+                            let _ = sender.send(delivery).await;
+                        }
+                    },
+                    cancel,
+                    None,
+                )
+                .await;
+            if let Err(e) = result {
+                tracing::error!(error = %e, "failed to receive messages");
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    async fn ack(&self, delivery: Self::Delivery) -> Result<()> {
+        delivery.received.ack().await?;
+        self.attempts
+            .lock()
+            .unwrap()
+            .remove(&delivery.received.message.message_id);
+
+        Ok(())
+    }
+
+    async fn nack(&self, delivery: Self::Delivery, delay: Duration) -> Result<()> {
+        // the client cannot extend the ack deadline of a message, so it is nacked once the delay
+        // is over, or redelivered when its ack deadline expires first
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if let Err(e) = delivery.received.nack().await {
+                tracing::warn!(error = %e, "failed to nack a message");
+            }
+        });
+
+        Ok(())
+    }
+}
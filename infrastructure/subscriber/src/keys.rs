@@ -1,96 +1,521 @@
 use super::pubsub_schema;
+use crate::broker::{published_at, Broker, Delivery, TENANT_ID};
 use crate::pubsub_schema::sync_keys::Payload;
-use anyhow::{anyhow, Result};
+use crate::retry::{HandleError, RetryPolicy};
+use anyhow::Result;
+use domain::dead_letters::{DeadLetterRepository, NewDeadLetter};
 use domain::error::DomainError;
+use domain::jobs::{JobRepository, JobStatus};
 use domain::keys::KeyRepository;
 use domain::objects::ObjectRepository;
-use domain::rules::RuleRepository;
+use domain::rules::{Rule, RuleRepository};
+use domain::tenants::TenantId;
+use metrics::MessageOutcome;
+use prost::Message;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use telemetry::request_id;
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
+/// How often a running job is checked for cancellation.
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How the keys of a rule are regenerated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyncOptions {
+    /// Number of keys written per transaction.
+    pub chunk_size: usize,
+    /// Number of chunks written at once.
+    pub parallelism: usize,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 1_000,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Where `KeysHandler::run` receives messages from and how they are handled.
 #[derive(Clone, Debug)]
-pub struct KeysHandler<R: RuleRepository, O: ObjectRepository, K: KeyRepository> {
+pub struct RunOptions {
+    pub topic: String,
+    pub subscription: String,
+    /// Number of messages handled at once. It should not exceed the size of the connection pool.
+    pub concurrency: usize,
+    pub retry_policy: RetryPolicy,
+    /// How long the messages in flight may take to finish on shutdown.
+    pub drain_timeout: Duration,
+}
+
+#[derive(Clone, Debug)]
+pub struct KeysHandler<R: RuleRepository, O: ObjectRepository, K: KeyRepository, J: JobRepository> {
     rule_repository: R,
     object_repository: O,
     key_repository: K,
+    job_repository: J,
+    options: SyncOptions,
 }
 
-impl<R: RuleRepository, O: ObjectRepository, K: KeyRepository> KeysHandler<R, O, K> {
-    pub fn new(rule_repository: R, object_repository: O, key_repository: K) -> Self {
+impl<R: RuleRepository, O: ObjectRepository, K: KeyRepository, J: JobRepository>
+    KeysHandler<R, O, K, J>
+{
+    pub fn new(
+        rule_repository: R,
+        object_repository: O,
+        key_repository: K,
+        job_repository: J,
+        options: SyncOptions,
+    ) -> Self {
         Self {
             rule_repository,
             object_repository,
             key_repository,
+            job_repository,
+            options,
+        }
+    }
+
+    /// Returns a copy of the handler whose repositories only see the data of `tenant_id`.
+    fn for_tenant(&self, tenant_id: &TenantId) -> Self {
+        Self {
+            rule_repository: self.rule_repository.for_tenant(tenant_id),
+            object_repository: self.object_repository.for_tenant(tenant_id),
+            key_repository: self.key_repository.for_tenant(tenant_id),
+            job_repository: self.job_repository.for_tenant(tenant_id),
+            options: self.options.clone(),
         }
     }
 
-    pub async fn main(&self, request: pubsub_schema::SyncKeys) -> Result<()> {
-        let payload = request.payload.ok_or(anyhow!("invalid payload"))?;
+    /// Handles the `SyncKeys` messages of a subscription until the broker stops delivering them
+    /// or `shutdown` is cancelled. Transient failures are retried with backoff, others end up in
+    /// the dead letters. On shutdown the messages in flight are given `options.drain_timeout` to
+    /// finish, the rest are handed back to the broker and resume from their checkpoint.
+    /// `subscribed` is set while messages are being received.
+    pub async fn run<B: Broker, D: DeadLetterRepository>(
+        &self,
+        broker: &B,
+        dead_letters: &D,
+        options: &RunOptions,
+        subscribed: &watch::Sender<bool>,
+        shutdown: CancellationToken,
+    ) -> Result<()> {
+        let semaphore = Arc::new(Semaphore::new(options.concurrency));
+        // cancelled when the messages in flight outlast the drain timeout
+        let abort = CancellationToken::new();
+        let mut deliveries = broker
+            .subscribe(&options.topic, &options.subscription, shutdown.clone())
+            .await?;
+        subscribed.send_replace(true);
+        loop {
+            let permit = tokio::select! {
+                permit = semaphore.clone().acquire_owned() => permit?,
+                _ = shutdown.cancelled() => break,
+            };
+            let delivery = tokio::select! {
+                delivery = deliveries.recv() => delivery,
+                _ = shutdown.cancelled() => None,
+            };
+            let Some(delivery) = delivery else {
+                break;
+            };
+            if let Some(lag) = published_at(delivery.message())
+                .and_then(|published_at| SystemTime::now().duration_since(published_at).ok())
+            {
+                metrics::observe_message_lag(lag);
+            }
+
+            let attributes = &delivery.message().attributes;
+            let span = tracing::info_span!(
+                "message",
+                event_id = attributes.get("event_id").map(String::as_str),
+                event_type = attributes.get("event_type").map(String::as_str),
+                request_id = attributes.get(request_id::ATTRIBUTE).map(String::as_str),
+                tenant_id = attributes.get(TENANT_ID).map(String::as_str),
+                attempt = delivery.attempt(),
+            );
 
-        match payload {
-            Payload::CreateKeysRequest(v) => self.create_keys(v).await,
-            Payload::UpdateKeysRequest(v) => self.update_keys(v).await,
-            Payload::DeleteKeysRequest(v) => self.delete_keys(v).await,
-        }?;
+            let handler = self.clone();
+            let broker = broker.clone();
+            let dead_letters = dead_letters.clone();
+            let options = options.clone();
+            let abort = abort.clone();
+            let task = async move {
+                let result = tokio::select! {
+                    result = handler.handle(&delivery.message().data) => Some(result),
+                    _ = abort.cancelled() => None,
+                };
+                let result = match result {
+                    Some(result) => {
+                        handler
+                            .settle(&broker, &dead_letters, &options, delivery, result)
+                            .await
+                    }
+                    None => {
+                        metrics::count_message(MessageOutcome::Nack);
+                        broker.nack(delivery, Duration::ZERO).await
+                    }
+                };
+                if let Err(e) = result {
+                    tracing::error!(error = %e, "failed to settle message");
+                }
+                drop(permit);
+            };
+            tokio::spawn(task.instrument(span));
+        }
+
+        subscribed.send_replace(false);
+
+        // Hand back the messages received but not started.
+        deliveries.close();
+        while let Some(delivery) = deliveries.recv().await {
+            metrics::count_message(MessageOutcome::Nack);
+            if let Err(e) = broker.nack(delivery, Duration::ZERO).await {
+                tracing::error!(error = %e, "failed to settle message");
+            }
+        }
+
+        // Wait for the messages in flight.
+        let concurrency = options.concurrency as u32;
+        if tokio::time::timeout(options.drain_timeout, semaphore.acquire_many(concurrency))
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                drain_timeout = ?options.drain_timeout,
+                "messages in flight did not finish in time, handing them back"
+            );
+            abort.cancel();
+            let _ = semaphore.acquire_many(concurrency).await?;
+        }
 
         Ok(())
     }
 
-    async fn create_keys(&self, request: pubsub_schema::CreateKeysRequest) -> Result<()> {
-        // get rule and objects
-        let rule = self
-            .rule_repository
-            .get(request.rule_id.clone())
-            .await?
-            .ok_or(anyhow!("Rule Notfound, id: [{}]", request.rule_id))?;
-        let objects = self.object_repository.find_all().await?;
-
-        // generate keys
-        let keys = objects
-            .into_iter()
-            .map(|object| {
-                let key = rule.generate_key(object.clone())?;
-                Ok((object.id, key))
+    async fn handle(&self, data: &[u8]) -> Result<(), HandleError> {
+        match pubsub_schema::SyncKeys::decode(data) {
+            Ok(request) => self.main(request).await,
+            Err(e) => Err(HandleError::Permanent(e.to_string())),
+        }
+    }
+
+    /// Acks a handled message, or retries or dead-letters it depending on the error.
+    async fn settle<B: Broker, D: DeadLetterRepository>(
+        &self,
+        broker: &B,
+        dead_letters: &D,
+        options: &RunOptions,
+        delivery: B::Delivery,
+        result: Result<(), HandleError>,
+    ) -> Result<()> {
+        let error = match result {
+            Ok(()) => {
+                tracing::info!("handled message");
+                metrics::count_message(MessageOutcome::Ack);
+                return broker.ack(delivery).await;
+            }
+            Err(e) => e,
+        };
+        let attempt = delivery.attempt();
+        tracing::warn!(attempt, error = %error, "failed to handle message");
+        if options.retry_policy.should_retry(&error, attempt) {
+            metrics::count_message(MessageOutcome::Nack);
+            return broker
+                .nack(delivery, options.retry_policy.backoff(attempt))
+                .await;
+        }
+
+        let message = delivery.message().clone();
+        let request = pubsub_schema::SyncKeys::decode(message.data.as_slice()).ok();
+        // messages that cannot be decoded are kept under the default tenant
+        let tenant_id = request
+            .as_ref()
+            .and_then(|request| message_tenant(&request.tenant_id).ok())
+            .unwrap_or_default();
+        if let Some(request) = request {
+            self.job_repository
+                .for_tenant(&tenant_id)
+                .finish(request.job_id, JobStatus::Failed, Some(error.to_string()))
+                .await?;
+            // the keys written so far are kept, a replay overwrites them from the first object
+            self.key_repository
+                .for_tenant(&tenant_id)
+                .delete_checkpoint(request.message_id)
+                .await?;
+        }
+        dead_letters
+            .create(NewDeadLetter {
+                tenant_id,
+                topic: options.topic.clone(),
+                subscription: options.subscription.clone(),
+                ordering_key: message.ordering_key,
+                data: message.data,
+                attributes: message.attributes,
+                error: error.to_string(),
+                attempts: attempt,
             })
-            .collect::<Result<Vec<_>, DomainError>>()
-            .map_err(|e| anyhow!(e.to_string()))?;
+            .await?;
+        metrics::count_message(MessageOutcome::DeadLetter);
+        broker.ack(delivery).await
+    }
 
-        // add new related key
-        if !keys.is_empty() {
-            self.key_repository.create_by_rule(rule.id, keys).await?;
+    /// Applies the message to the data of its tenant.
+    pub async fn main(&self, request: pubsub_schema::SyncKeys) -> Result<(), HandleError> {
+        let tenant_id = message_tenant(&request.tenant_id)?;
+        self.for_tenant(&tenant_id).apply(request).await
+    }
+
+    async fn apply(&self, request: pubsub_schema::SyncKeys) -> Result<(), HandleError> {
+        if request.message_id.is_empty() {
+            return Err(HandleError::Permanent("message id is missing".to_string()));
+        }
+        // the message was redelivered after it had been applied
+        if self
+            .key_repository
+            .is_processed(request.message_id.clone())
+            .await?
+        {
+            return Ok(());
+        }
+
+        let payload = request
+            .payload
+            .ok_or(HandleError::Permanent("invalid payload".to_string()))?;
+
+        // skip a job cancelled before it started; messages published before jobs were tracked have none
+        let total_objects = match payload {
+            Payload::DeleteKeysRequest(_) => None,
+            _ => Some(self.object_repository.count().await?),
+        };
+        let job = self.job_repository.get(request.job_id.clone()).await?;
+        if job.is_some()
+            && !self
+                .job_repository
+                .start(request.job_id.clone(), total_objects)
+                .await?
+        {
+            self.key_repository
+                .complete_sync(request.message_id)
+                .await?;
+            return Ok(());
         }
 
+        let cancellation = CancellationToken::new();
+        // stops the watcher however the sync ends, including when it is dropped on shutdown
+        let _watcher = cancellation.clone().drop_guard();
+        self.watch_job(request.job_id.clone(), cancellation.clone());
+        let result = match payload {
+            Payload::CreateKeysRequest(v) => {
+                self.create_keys(
+                    v,
+                    request.message_id,
+                    request.job_id.clone(),
+                    request.rule_version,
+                    &cancellation,
+                )
+                .await
+            }
+            Payload::UpdateKeysRequest(v) => {
+                self.update_keys(
+                    v,
+                    request.message_id,
+                    request.job_id.clone(),
+                    request.rule_version,
+                    &cancellation,
+                )
+                .await
+            }
+            Payload::DeleteKeysRequest(v) => self.delete_keys(v, request.message_id).await,
+        };
+        result?;
+
+        self.job_repository
+            .finish(request.job_id, JobStatus::Succeeded, None)
+            .await?;
+
         Ok(())
     }
 
-    async fn update_keys(&self, request: pubsub_schema::UpdateKeysRequest) -> Result<()> {
-        // get rule and objects
-        let rule = self
-            .rule_repository
-            .get(request.rule_id.clone())
-            .await?
-            .ok_or(anyhow!("Rule Notfound, id: [{}]", request.rule_id))?;
-        let objects = self.object_repository.find_all().await?;
-
-        // generate keys
-        let keys = objects
-            .into_iter()
-            .map(|object| {
-                let key = rule.generate_key(object.clone())?;
-                Ok((object.id, key))
-            })
-            .collect::<Result<Vec<_>, DomainError>>()
-            .map_err(|e| anyhow!(e.to_string()))?;
+    /// Cancels `cancellation` once the job has been cancelled through the API.
+    /// Watching stops when `cancellation` is cancelled.
+    fn watch_job(&self, job_id: String, cancellation: CancellationToken) {
+        let job_repository = self.job_repository.clone();
+        let watcher = async move {
+            let mut interval = tokio::time::interval(JOB_POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = cancellation.cancelled() => break,
+                }
+                match job_repository.get(job_id.clone()).await {
+                    Ok(Some(job)) if job.status == JobStatus::Cancelled => {
+                        cancellation.cancel();
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!(job_id, error = %e, "failed to read job"),
+                }
+            }
+        };
+        tokio::spawn(watcher.in_current_span());
+    }
 
-        // update new related key
-        if !keys.is_empty() {
-            self.key_repository.update_by_rule(rule.id, keys).await?;
+    /// Returns the rule the keys are generated from, or `None` when it has been updated or deleted
+    /// since the message was published; the keys are then synced by a later message.
+    async fn get_current_rule(
+        &self,
+        rule_id: String,
+        rule_version: i64,
+    ) -> Result<Option<Rule>, HandleError> {
+        let rule = self.rule_repository.get(rule_id).await?;
+
+        Ok(rule.filter(|rule| rule.version <= rule_version))
+    }
+
+    /// Writes the keys of every object in chunks ordered by object id. A checkpoint is saved
+    /// after each batch of chunks, so that a redelivered message resumes where it stopped.
+    /// The sync is not atomic: until it completes, the objects not reached yet keep the keys of
+    /// the previous version of the rule, and a cancelled or dead-lettered sync leaves them so.
+    /// Objects whose key cannot be generated are logged, counted as skipped in the job and
+    /// left without a key for the rule, since they would fail again on every retry.
+    async fn sync_keys(
+        &self,
+        rule: Rule,
+        message_id: String,
+        job_id: String,
+        cancellation: &CancellationToken,
+    ) -> Result<(), HandleError> {
+        let batch_size = self.options.chunk_size * self.options.parallelism;
+        let mut after = self
+            .key_repository
+            .find_checkpoint(message_id.clone())
+            .await?;
+        loop {
+            // a cancelled job leaves the keys written so far
+            if cancellation.is_cancelled() {
+                break;
+            }
+
+            let objects = self
+                .object_repository
+                .find_page(after, batch_size as u64)
+                .await?;
+            let Some(last_object_id) = objects.last().map(|object| object.id.clone()) else {
+                break;
+            };
+            let processed_objects = objects.len();
+            let is_last = processed_objects < batch_size;
+
+            // generate keys
+            let mut keys = Vec::with_capacity(processed_objects);
+            let mut skipped = Vec::new();
+            for object in objects {
+                match rule.generate_key(object.clone()) {
+                    Ok(key) => keys.push((object.id, key)),
+                    Err(e) => {
+                        tracing::warn!(object_id = %object.id, error = %e, "skipped object");
+                        skipped.push(object.id);
+                    }
+                }
+            }
+            let skipped_objects = skipped.len();
+
+            // write the chunks of the batch at once
+            let mut writes = JoinSet::new();
+            for chunk in keys.chunks(self.options.chunk_size) {
+                let key_repository = self.key_repository.clone();
+                let rule_id = rule.id.clone();
+                let chunk = chunk.to_vec();
+                writes.spawn(async move { key_repository.upsert_by_rule(rule_id, chunk).await });
+            }
+            while let Some(result) = writes.join_next().await {
+                result.map_err(|e| HandleError::Transient(e.to_string()))??;
+            }
+            metrics::add_keys_generated(keys.len());
+            // drop the keys the objects had under the previous version of the rule
+            self.key_repository
+                .delete_by_objects(rule.id.clone(), skipped)
+                .await?;
+
+            self.key_repository
+                .save_checkpoint(message_id.clone(), rule.id.clone(), last_object_id.clone())
+                .await?;
+            self.job_repository
+                .add_progress(
+                    job_id.clone(),
+                    processed_objects as u64,
+                    skipped_objects as u64,
+                )
+                .await?;
+            if is_last {
+                break;
+            }
+            after = Some(last_object_id);
         }
 
+        self.key_repository.complete_sync(message_id).await?;
+
         Ok(())
     }
 
-    async fn delete_keys(&self, request: pubsub_schema::DeleteKeysRequest) -> Result<()> {
-        self.key_repository.delete_by_rule(request.rule_id).await?;
+    async fn create_keys(
+        &self,
+        request: pubsub_schema::CreateKeysRequest,
+        message_id: String,
+        job_id: String,
+        rule_version: i64,
+        cancellation: &CancellationToken,
+    ) -> Result<(), HandleError> {
+        let Some(rule) = self.get_current_rule(request.rule_id, rule_version).await? else {
+            self.key_repository.complete_sync(message_id).await?;
+            return Ok(());
+        };
+
+        // add new related key
+        self.sync_keys(rule, message_id, job_id, cancellation).await
+    }
+
+    /// Overwrites the key of every object. The old keys need not be deleted first, since
+    /// the ones of skipped objects are deleted as they are met.
+    async fn update_keys(
+        &self,
+        request: pubsub_schema::UpdateKeysRequest,
+        message_id: String,
+        job_id: String,
+        rule_version: i64,
+        cancellation: &CancellationToken,
+    ) -> Result<(), HandleError> {
+        let Some(rule) = self.get_current_rule(request.rule_id, rule_version).await? else {
+            self.key_repository.complete_sync(message_id).await?;
+            return Ok(());
+        };
+
+        // update new related key
+        self.sync_keys(rule, message_id, job_id, cancellation).await
+    }
+
+    async fn delete_keys(
+        &self,
+        request: pubsub_schema::DeleteKeysRequest,
+        message_id: String,
+    ) -> Result<(), HandleError> {
+        self.key_repository
+            .delete_by_rule(request.rule_id, message_id)
+            .await?;
 
         Ok(())
     }
 }
+
+/// Messages published before tenants existed carry none and belong to the default tenant.
+fn message_tenant(tenant_id: &str) -> Result<TenantId, DomainError> {
+    match tenant_id {
+        "" => Ok(TenantId::default()),
+        tenant_id => TenantId::parse(tenant_id),
+    }
+}
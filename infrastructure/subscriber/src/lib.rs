@@ -1,3 +1,8 @@
+pub mod broker;
 pub mod keys;
+pub mod outbox;
+pub mod publisher;
+pub mod retention;
+pub mod retry;
 
 pub mod pubsub_schema;
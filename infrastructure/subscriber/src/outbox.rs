@@ -0,0 +1,35 @@
+use domain::events::{EventPublisher, OutboxRepository};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Drains the outbox to the message broker. An event is published at least once.
+#[derive(Clone, Debug)]
+pub struct OutboxRelay<R: OutboxRepository, P: EventPublisher> {
+    repository: R,
+    publisher: P,
+}
+
+impl<R: OutboxRepository, P: EventPublisher> OutboxRelay<R, P> {
+    pub fn new(repository: R, publisher: P) -> Self {
+        Self {
+            repository,
+            publisher,
+        }
+    }
+
+    /// Relays events until `shutdown` is cancelled. A batch being relayed is finished first.
+    pub async fn run(&self, batch_size: u64, interval: Duration, shutdown: &CancellationToken) {
+        while !shutdown.is_cancelled() {
+            match self.repository.relay(&self.publisher, batch_size).await {
+                // keep draining while there is a backlog
+                Ok(published) if published as u64 == batch_size => continue,
+                Ok(_) => {}
+                Err(e) => tracing::error!(error = %e, "failed to relay outbox events"),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = shutdown.cancelled() => {}
+            }
+        }
+    }
+}
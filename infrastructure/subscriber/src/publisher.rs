@@ -0,0 +1,141 @@
+use crate::broker::{Broker, Message as BrokerMessage, PUBLISHED_AT, TENANT_ID};
+use crate::pubsub_schema::sync_keys::Payload;
+use crate::pubsub_schema::{
+    ChangeType, CreateKeysRequest, DeleteKeysRequest, MessageType, ObjectChanged, SyncKeys,
+    UpdateKeysRequest,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use database::client::outbox::{
+    CREATE_KEYS, DELETE_KEYS, OBJECT_CREATED, OBJECT_DELETED, OBJECT_UPDATED, UPDATE_KEYS,
+};
+use domain::error::DomainError;
+use domain::events::{Event, EventPublisher, ObjectChange, OutboxEvent};
+use domain::jobs::KeySyncJob;
+use prost::Message;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use telemetry::request_id;
+
+/// Publishes `SyncKeys` jobs to the worker topic and `ObjectChanged` notifications to the object topic.
+#[derive(Clone, Debug)]
+pub struct BrokerEventPublisher<B: Broker> {
+    broker: B,
+    keys_topic_id: String,
+    objects_topic_id: String,
+}
+
+impl<B: Broker> BrokerEventPublisher<B> {
+    pub fn new(broker: B, keys_topic_id: String, objects_topic_id: String) -> Self {
+        Self {
+            broker,
+            keys_topic_id,
+            objects_topic_id,
+        }
+    }
+}
+
+#[async_trait]
+impl<B: Broker> EventPublisher for BrokerEventPublisher<B> {
+    async fn publish(&self, event: OutboxEvent) -> Result<(), DomainError> {
+        let (topic, event_type, aggregate_id, data) = match event.event {
+            Event::KeySync(job) => {
+                let (event_type, rule_id, rule_version, message_type, payload) = match job {
+                    KeySyncJob::Create {
+                        rule_id,
+                        rule_version,
+                    } => (
+                        CREATE_KEYS,
+                        rule_id.clone(),
+                        rule_version,
+                        MessageType::CreateKeysRequest,
+                        Payload::CreateKeysRequest(CreateKeysRequest { rule_id }),
+                    ),
+                    KeySyncJob::Update {
+                        rule_id,
+                        rule_version,
+                    } => (
+                        UPDATE_KEYS,
+                        rule_id.clone(),
+                        rule_version,
+                        MessageType::UpdateKeysRequest,
+                        Payload::UpdateKeysRequest(UpdateKeysRequest { rule_id }),
+                    ),
+                    KeySyncJob::Delete {
+                        rule_id,
+                        rule_version,
+                    } => (
+                        DELETE_KEYS,
+                        rule_id.clone(),
+                        rule_version,
+                        MessageType::DeleteKeysRequest,
+                        Payload::DeleteKeysRequest(DeleteKeysRequest { rule_id }),
+                    ),
+                };
+                let request = SyncKeys {
+                    message_type: message_type as i32,
+                    payload: Some(payload),
+                    job_id: event.event_id.clone(),
+                    message_id: event.event_id.clone(),
+                    rule_version,
+                    tenant_id: event.tenant_id.to_string(),
+                };
+                (
+                    &self.keys_topic_id,
+                    event_type,
+                    rule_id,
+                    request.encode_to_vec(),
+                )
+            }
+            Event::ObjectChanged { object_id, change } => {
+                let (event_type, change_type) = match change {
+                    ObjectChange::Created => (OBJECT_CREATED, ChangeType::Created),
+                    ObjectChange::Updated => (OBJECT_UPDATED, ChangeType::Updated),
+                    ObjectChange::Deleted => (OBJECT_DELETED, ChangeType::Deleted),
+                };
+                let notification = ObjectChanged {
+                    event_id: event.event_id.clone(),
+                    object_id: object_id.clone(),
+                    change_type: change_type as i32,
+                    tenant_id: event.tenant_id.to_string(),
+                };
+                (
+                    &self.objects_topic_id,
+                    event_type,
+                    object_id,
+                    notification.encode_to_vec(),
+                )
+            }
+        };
+
+        let published_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        // consumers order and deduplicate events with these attributes, and measure their lag;
+        // the tenant lets subscriptions filter the events of a tenant
+        let mut attributes = HashMap::from([
+            ("sequence".to_string(), event.sequence.to_string()),
+            ("event_id".to_string(), event.event_id),
+            ("event_type".to_string(), event_type.to_string()),
+            (TENANT_ID.to_string(), event.tenant_id.to_string()),
+            (PUBLISHED_AT.to_string(), published_at.to_string()),
+        ]);
+        // correlates the async work with the request that caused it
+        if let Some(request_id) = event.request_id {
+            attributes.insert(request_id::ATTRIBUTE.to_string(), request_id);
+        }
+        let message = BrokerMessage {
+            data,
+            attributes,
+            // events of an aggregate are delivered in the order they were published
+            ordering_key: aggregate_id,
+        };
+        self.broker
+            .publish(topic, message)
+            .await
+            .map_err(|e| DomainError::Unavailable(e.to_string()))?;
+
+        Ok(())
+    }
+}
@@ -1,8 +1,64 @@
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ObjectChanged {
+    #[prost(string, tag = "1")]
+    pub event_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub object_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "ChangeType", tag = "3")]
+    pub change_type: i32,
+    #[prost(string, tag = "4")]
+    pub tenant_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ChangeType {
+    Unspecified = 0,
+    Created = 1,
+    Updated = 2,
+    Deleted = 3,
+}
+impl ChangeType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ChangeType::Unspecified => "CHANGE_TYPE_UNSPECIFIED",
+            ChangeType::Created => "CHANGE_TYPE_CREATED",
+            ChangeType::Updated => "CHANGE_TYPE_UPDATED",
+            ChangeType::Deleted => "CHANGE_TYPE_DELETED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CHANGE_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
+            "CHANGE_TYPE_CREATED" => Some(Self::Created),
+            "CHANGE_TYPE_UPDATED" => Some(Self::Updated),
+            "CHANGE_TYPE_DELETED" => Some(Self::Deleted),
+            _ => None,
+        }
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SyncKeys {
     #[prost(enumeration = "MessageType", tag = "1")]
     pub message_type: i32,
+    #[prost(string, tag = "5")]
+    pub job_id: ::prost::alloc::string::String,
+    /// Unique per message, so that redelivered messages are applied only once.
+    #[prost(string, tag = "6")]
+    pub message_id: ::prost::alloc::string::String,
+    /// Version of the rule the message was published for.
+    #[prost(int64, tag = "7")]
+    pub rule_version: i64,
+    /// Tenant of the rule. Messages published before tenants existed have none and belong to
+    /// the default tenant.
+    #[prost(string, tag = "8")]
+    pub tenant_id: ::prost::alloc::string::String,
     #[prost(oneof = "sync_keys::Payload", tags = "2, 3, 4")]
     pub payload: ::core::option::Option<sync_keys::Payload>,
 }
@@ -0,0 +1,42 @@
+use chrono::{Duration as ChronoDuration, FixedOffset, Utc};
+use domain::keys::ProcessedMessageRepository;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Forgets the ids of applied messages once they can no longer be redelivered.
+#[derive(Clone, Debug)]
+pub struct ProcessedMessageRetention<R: ProcessedMessageRepository> {
+    repository: R,
+    retention: Duration,
+}
+
+impl<R: ProcessedMessageRepository> ProcessedMessageRetention<R> {
+    /// `retention` should exceed the time the broker retains unacknowledged messages.
+    pub fn new(repository: R, retention: Duration) -> Self {
+        Self {
+            repository,
+            retention,
+        }
+    }
+
+    /// Deletes expired ids every `interval` until `shutdown` is cancelled.
+    pub async fn run(&self, interval: Duration, shutdown: &CancellationToken) {
+        while !shutdown.is_cancelled() {
+            let now = Utc::now().with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap());
+            // nothing expires when the retention is out of the range of timestamps
+            let before = ChronoDuration::from_std(self.retention)
+                .ok()
+                .and_then(|retention| now.checked_sub_signed(retention));
+            if let Some(before) = before {
+                match self.repository.delete_before(before).await {
+                    Ok(deleted) => tracing::debug!(deleted, "deleted expired processed messages"),
+                    Err(e) => tracing::error!(error = %e, "failed to delete processed messages"),
+                }
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = shutdown.cancelled() => {}
+            }
+        }
+    }
+}
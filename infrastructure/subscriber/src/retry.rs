@@ -0,0 +1,137 @@
+use domain::error::DomainError;
+use rand::Rng;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HandleError {
+    /// Might succeed when the message is delivered again, e.g. the database is unavailable.
+    #[error("Transient Error: [{0}]")]
+    Transient(String),
+    /// Fails on every delivery, e.g. the message cannot be decoded.
+    #[error("Permanent Error: [{0}]")]
+    Permanent(String),
+}
+
+impl From<DomainError> for HandleError {
+    fn from(value: DomainError) -> Self {
+        match value {
+            DomainError::NotFound(_)
+            | DomainError::InvalidArgument(_)
+            | DomainError::AlreadyExists(_)
+            | DomainError::FailedPrecondition(_)
+            | DomainError::Unauthenticated(_)
+            | DomainError::PermissionDenied(_) => HandleError::Permanent(value.to_string()),
+            DomainError::Conflict(_) | DomainError::Unavailable(_) | DomainError::Unexpected(_) => {
+                HandleError::Transient(value.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Deliveries of a message before it is moved to the dead letters.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the next delivery of a message that failed on its `attempt`-th delivery.
+    /// The delay doubles on every attempt up to `max_backoff`, and its second half is random so
+    /// that messages failing together are not delivered again together.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+        let half = backoff / 2;
+
+        half + (backoff - half).mul_f64(rand::thread_rng().gen())
+    }
+
+    pub fn should_retry(&self, error: &HandleError, attempt: u32) -> bool {
+        matches!(error, HandleError::Transient(_)) && attempt < self.max_attempts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_max_with_jitter() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+        };
+        let cases = [
+            (0, 1),
+            (1, 1),
+            (2, 2),
+            (3, 4),
+            (4, 8),
+            (5, 10),
+            (u32::MAX, 10),
+        ];
+        for (attempt, secs) in cases {
+            let max = Duration::from_secs(secs);
+            for _ in 0..100 {
+                let backoff = policy.backoff(attempt);
+                assert!(
+                    max / 2 <= backoff && backoff <= max,
+                    "attempt {}: {:?} not in [{:?}, {:?}]",
+                    attempt,
+                    backoff,
+                    max / 2,
+                    max
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn only_transient_errors_are_retried_until_the_last_attempt() {
+        let policy = RetryPolicy::default();
+        let transient = HandleError::Transient(String::new());
+        let permanent = HandleError::Permanent(String::new());
+
+        assert!(policy.should_retry(&transient, 1));
+        assert!(policy.should_retry(&transient, policy.max_attempts - 1));
+        assert!(!policy.should_retry(&transient, policy.max_attempts));
+        assert!(!policy.should_retry(&permanent, 1));
+    }
+
+    #[test]
+    fn domain_errors_are_classified() {
+        let cases = [
+            (DomainError::NotFound(String::new()), false),
+            (DomainError::InvalidArgument(String::new()), false),
+            (DomainError::AlreadyExists(String::new()), false),
+            (DomainError::FailedPrecondition(String::new()), false),
+            (DomainError::Unauthenticated(String::new()), false),
+            (DomainError::PermissionDenied(String::new()), false),
+            (DomainError::Conflict(String::new()), true),
+            (DomainError::Unavailable(String::new()), true),
+            (DomainError::Unexpected(String::new()), true),
+        ];
+        for (error, transient) in cases {
+            let name = format!("{:?}", error);
+            let is_transient = matches!(HandleError::from(error), HandleError::Transient(_));
+            assert_eq!(is_transient, transient, "{}", name);
+        }
+    }
+}
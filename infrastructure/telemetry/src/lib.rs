@@ -0,0 +1,85 @@
+use anyhow::Result;
+use opentelemetry::sdk::{trace, Resource};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use std::env;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+pub mod request_id;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogOptions {
+    /// Default level, refined by `RUST_LOG` directives when it is set. sqlx logs at `warn` unless
+    /// `RUST_LOG` says otherwise.
+    pub level: String,
+    /// Writes one JSON object per event instead of text.
+    pub json: bool,
+    /// gRPC endpoint of an OTLP collector spans are exported to, e.g. `http://localhost:4317`.
+    pub otlp_endpoint: Option<String>,
+}
+
+/// Flushes spans not exported yet when dropped.
+#[derive(Debug)]
+pub struct Telemetry {
+    otlp: bool,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if self.otlp {
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
+/// Installs the global subscriber, which writes events to stderr and, when an OTLP endpoint is
+/// given, exports spans as `service_name`. Must be called within a Tokio runtime.
+pub fn init(service_name: &'static str, options: &LogOptions) -> Result<Telemetry> {
+    // sqlx logs every statement at info
+    let filter = EnvFilter::try_new(format!(
+        "{},sqlx=warn,{}",
+        options.level,
+        env::var(EnvFilter::DEFAULT_ENV).unwrap_or_default()
+    ))?;
+    let fmt = if options.json {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_writer(std::io::stderr)
+            .boxed()
+    } else {
+        tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .boxed()
+    };
+    let otlp = match &options.otlp_endpoint {
+        Some(endpoint) => {
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint),
+                )
+                .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", service_name),
+                ])))
+                .install_batch(opentelemetry::runtime::Tokio)?;
+            Some(tracing_opentelemetry::layer().with_tracer(tracer))
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt)
+        .with(otlp)
+        .try_init()?;
+
+    Ok(Telemetry {
+        otlp: options.otlp_endpoint.is_some(),
+    })
+}
@@ -0,0 +1,27 @@
+use std::future::Future;
+use uuid::Uuid;
+
+/// gRPC metadata key a request id is read from and echoed in.
+pub const HEADER: &str = "x-request-id";
+/// Message attribute the request id is propagated to the worker in.
+pub const ATTRIBUTE: &str = "request_id";
+/// Longest request id accepted from clients. Longer ones are replaced.
+pub const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+pub fn generate() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Runs `future` with `id` as the request id of the current task.
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(id, future).await
+}
+
+/// Returns the request id of the current task, if it runs within `scope`.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
@@ -1,12 +1,48 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20230601_000001_create_export_indexes;
+mod m20230605_000001_add_object_source;
+mod m20230610_000001_create_keys_rule_id_key_index;
+mod m20230615_000001_create_outbox;
+mod m20230620_000001_create_broker_messages;
+mod m20230625_000001_create_dead_letters;
+mod m20230701_000001_add_rule_version;
+mod m20230705_000001_create_processed_messages;
+mod m20230710_000001_create_key_sync_checkpoints;
+mod m20230715_000001_create_jobs;
+mod m20230720_000001_add_outbox_request_id;
+mod m20230725_000001_create_api_keys;
+mod m20230730_000001_create_role_bindings;
+mod m20230805_000001_add_tenant_id;
+mod m20230810_000001_add_outbox_xid;
+mod m20230815_000001_add_job_skipped_objects;
+mod m20230820_000001_unquote_keys;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20230601_000001_create_export_indexes::Migration),
+            Box::new(m20230605_000001_add_object_source::Migration),
+            Box::new(m20230610_000001_create_keys_rule_id_key_index::Migration),
+            Box::new(m20230615_000001_create_outbox::Migration),
+            Box::new(m20230620_000001_create_broker_messages::Migration),
+            Box::new(m20230625_000001_create_dead_letters::Migration),
+            Box::new(m20230701_000001_add_rule_version::Migration),
+            Box::new(m20230705_000001_create_processed_messages::Migration),
+            Box::new(m20230710_000001_create_key_sync_checkpoints::Migration),
+            Box::new(m20230715_000001_create_jobs::Migration),
+            Box::new(m20230720_000001_add_outbox_request_id::Migration),
+            Box::new(m20230725_000001_create_api_keys::Migration),
+            Box::new(m20230730_000001_create_role_bindings::Migration),
+            Box::new(m20230805_000001_add_tenant_id::Migration),
+            Box::new(m20230810_000001_add_outbox_xid::Migration),
+            Box::new(m20230815_000001_add_job_skipped_objects::Migration),
+            Box::new(m20230820_000001_unquote_keys::Migration),
+        ]
     }
 }
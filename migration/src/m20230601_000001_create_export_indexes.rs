@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Objects
+        manager
+            .create_index(
+                Index::create()
+                    .name("objects_updated_at_id_idx")
+                    .table(Objects::Table)
+                    .col(Objects::UpdatedAt)
+                    .col(Objects::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Keys
+        manager
+            .create_index(
+                Index::create()
+                    .name("keys_updated_at_rule_id_object_id_idx")
+                    .table(Keys::Table)
+                    .col(Keys::UpdatedAt)
+                    .col(Keys::RuleId)
+                    .col(Keys::ObjectId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("objects_updated_at_id_idx")
+                    .table(Objects::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("keys_updated_at_rule_id_object_id_idx")
+                    .table(Keys::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Objects {
+    Table,
+    Id,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Keys {
+    Table,
+    RuleId,
+    ObjectId,
+    UpdatedAt,
+}
@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Objects::Table)
+                    .add_column(ColumnDef::new(Objects::Source).string())
+                    .add_column(ColumnDef::new(Objects::ExternalId).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("objects_source_external_id_key")
+                    .table(Objects::Table)
+                    .col(Objects::Source)
+                    .col(Objects::ExternalId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("objects_source_external_id_key")
+                    .table(Objects::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Objects::Table)
+                    .drop_column(Objects::Source)
+                    .drop_column(Objects::ExternalId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Objects {
+    Table,
+    Source,
+    ExternalId,
+}
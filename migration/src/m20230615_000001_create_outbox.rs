@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Outbox::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Outbox::Sequence)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Outbox::EventId).string().not_null())
+                    .col(ColumnDef::new(Outbox::EventType).string().not_null())
+                    .col(ColumnDef::new(Outbox::AggregateId).string().not_null())
+                    .col(
+                        ColumnDef::new(Outbox::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Outbox::PublishedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        // the relay only reads events that have not been published yet
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE INDEX "outbox_unpublished_idx" ON "outbox" ("sequence") WHERE "published_at" IS NULL"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Outbox::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Outbox {
+    Table,
+    Sequence,
+    EventId,
+    EventType,
+    AggregateId,
+    CreatedAt,
+    PublishedAt,
+}
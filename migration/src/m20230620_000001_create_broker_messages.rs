@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BrokerMessages::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BrokerMessages::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BrokerMessages::Topic).string().not_null())
+                    .col(
+                        ColumnDef::new(BrokerMessages::OrderingKey)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BrokerMessages::Data).binary().not_null())
+                    .col(
+                        ColumnDef::new(BrokerMessages::Attributes)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BrokerMessages::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(BrokerMessages::VisibleAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(BrokerMessages::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("broker_messages_topic_ordering_key_id_idx")
+                    .table(BrokerMessages::Table)
+                    .col(BrokerMessages::Topic)
+                    .col(BrokerMessages::OrderingKey)
+                    .col(BrokerMessages::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BrokerMessages::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum BrokerMessages {
+    Table,
+    Id,
+    Topic,
+    OrderingKey,
+    Data,
+    Attributes,
+    Attempts,
+    VisibleAt,
+    CreatedAt,
+}
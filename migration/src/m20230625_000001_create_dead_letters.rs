@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DeadLetters::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DeadLetters::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(DeadLetters::Topic).string().not_null())
+                    .col(
+                        ColumnDef::new(DeadLetters::Subscription)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeadLetters::OrderingKey).string().not_null())
+                    .col(ColumnDef::new(DeadLetters::Data).binary().not_null())
+                    .col(
+                        ColumnDef::new(DeadLetters::Attributes)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeadLetters::Error).text().not_null())
+                    .col(ColumnDef::new(DeadLetters::Attempts).integer().not_null())
+                    .col(
+                        ColumnDef::new(DeadLetters::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DeadLetters::ReplayedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DeadLetters::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum DeadLetters {
+    Table,
+    Id,
+    Topic,
+    Subscription,
+    OrderingKey,
+    Data,
+    Attributes,
+    Error,
+    Attempts,
+    CreatedAt,
+    ReplayedAt,
+}
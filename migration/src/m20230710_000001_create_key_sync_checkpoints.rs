@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(KeySyncCheckpoints::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(KeySyncCheckpoints::MessageId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(KeySyncCheckpoints::RuleId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(KeySyncCheckpoints::LastObjectId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(KeySyncCheckpoints::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(KeySyncCheckpoints::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum KeySyncCheckpoints {
+    Table,
+    MessageId,
+    RuleId,
+    LastObjectId,
+    UpdatedAt,
+}
@@ -0,0 +1,166 @@
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::EnumIter;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Enums
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(JobType::Table)
+                    .values([
+                        JobType::CreateKeys,
+                        JobType::UpdateKeys,
+                        JobType::DeleteKeys,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(JobStatus::Table)
+                    .values([
+                        JobStatus::Pending,
+                        JobStatus::Running,
+                        JobStatus::Succeeded,
+                        JobStatus::Failed,
+                        JobStatus::Cancelled,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Jobs::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Jobs::Id).string().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Jobs::Type)
+                            .enumeration(
+                                JobType::Table,
+                                [
+                                    JobType::CreateKeys,
+                                    JobType::UpdateKeys,
+                                    JobType::DeleteKeys,
+                                ],
+                            )
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Jobs::RuleId).string().not_null())
+                    .col(
+                        ColumnDef::new(Jobs::Status)
+                            .enumeration(
+                                JobStatus::Table,
+                                [
+                                    JobStatus::Pending,
+                                    JobStatus::Running,
+                                    JobStatus::Succeeded,
+                                    JobStatus::Failed,
+                                    JobStatus::Cancelled,
+                                ],
+                            )
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Jobs::ProcessedObjects)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Jobs::TotalObjects).big_integer())
+                    .col(ColumnDef::new(Jobs::Error).text())
+                    .col(
+                        ColumnDef::new(Jobs::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Jobs::StartedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Jobs::FinishedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(Jobs::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // jobs are listed newest first
+        manager
+            .create_index(
+                Index::create()
+                    .name("jobs_created_at_id_idx")
+                    .table(Jobs::Table)
+                    .col(Jobs::CreatedAt)
+                    .col(Jobs::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Jobs::Table).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(JobStatus::Table).to_owned())
+            .await?;
+        manager
+            .drop_type(Type::drop().name(JobType::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Jobs {
+    Table,
+    Id,
+    Type,
+    RuleId,
+    Status,
+    ProcessedObjects,
+    TotalObjects,
+    Error,
+    CreatedAt,
+    StartedAt,
+    FinishedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum JobType {
+    Table,
+    #[iden = "CreateKeys"]
+    CreateKeys,
+    #[iden = "UpdateKeys"]
+    UpdateKeys,
+    #[iden = "DeleteKeys"]
+    DeleteKeys,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum JobStatus {
+    Table,
+    #[iden = "Pending"]
+    Pending,
+    #[iden = "Running"]
+    Running,
+    #[iden = "Succeeded"]
+    Succeeded,
+    #[iden = "Failed"]
+    Failed,
+    #[iden = "Cancelled"]
+    Cancelled,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RoleBindings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RoleBindings::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RoleBindings::Subject).string().not_null())
+                    // names of `domain::auth::Role`, not an enum so that roles can be added freely
+                    .col(ColumnDef::new(RoleBindings::Role).string().not_null())
+                    .col(
+                        ColumnDef::new(RoleBindings::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("role_bindings_subject_role_idx")
+                    .table(RoleBindings::Table)
+                    .col(RoleBindings::Subject)
+                    .col(RoleBindings::Role)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RoleBindings::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum RoleBindings {
+    Table,
+    Id,
+    Subject,
+    Role,
+    CreatedAt,
+}
@@ -0,0 +1,417 @@
+use sea_orm_migration::prelude::*;
+
+const DEFAULT_TENANT: &str = "default";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // existing rows belong to the default tenant
+        for table in tables() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(
+                            ColumnDef::new(TenantId)
+                                .string()
+                                .not_null()
+                                .default(DEFAULT_TENANT),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // targets of the foreign keys below
+        for (name, table) in [
+            ("fields_tenant_id_id_key", Fields::Table.into_iden()),
+            ("rules_tenant_id_id_key", Rules::Table.into_iden()),
+            ("objects_tenant_id_id_key", Objects::Table.into_iden()),
+        ] {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(name)
+                        .table(table)
+                        .col(TenantId)
+                        .col(Id)
+                        .unique()
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // rules only use fields, and keys only join rules and objects, of their own tenant
+        replace_foreign_key(
+            manager,
+            Rules::Table,
+            "rules_fk_fields",
+            ForeignKey::create()
+                .name("rules_fk_fields")
+                .from_tbl(Rules::Table)
+                .from_col(TenantId)
+                .from_col(Rules::FieldId)
+                .to_tbl(Fields::Table)
+                .to_col(TenantId)
+                .to_col(Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+        replace_foreign_key(
+            manager,
+            Keys::Table,
+            "keys_fk_rules",
+            ForeignKey::create()
+                .name("keys_fk_rules")
+                .from_tbl(Keys::Table)
+                .from_col(TenantId)
+                .from_col(Keys::RuleId)
+                .to_tbl(Rules::Table)
+                .to_col(TenantId)
+                .to_col(Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+        replace_foreign_key(
+            manager,
+            Keys::Table,
+            "keys_fk_objects",
+            ForeignKey::create()
+                .name("keys_fk_objects")
+                .from_tbl(Keys::Table)
+                .from_col(TenantId)
+                .from_col(Keys::ObjectId)
+                .to_tbl(Objects::Table)
+                .to_col(TenantId)
+                .to_col(Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+
+        // unique and export indexes per tenant
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("objects_source_external_id_key")
+                    .table(Objects::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("objects_tenant_id_source_external_id_key")
+                    .table(Objects::Table)
+                    .col(TenantId)
+                    .col(Objects::Source)
+                    .col(Objects::ExternalId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("role_bindings_subject_role_idx")
+                    .table(RoleBindings::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("role_bindings_tenant_id_subject_role_idx")
+                    .table(RoleBindings::Table)
+                    .col(TenantId)
+                    .col(RoleBindings::Subject)
+                    .col(RoleBindings::Role)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("objects_updated_at_id_idx")
+                    .table(Objects::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("objects_tenant_id_updated_at_id_idx")
+                    .table(Objects::Table)
+                    .col(TenantId)
+                    .col(Objects::UpdatedAt)
+                    .col(Id)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("keys_updated_at_rule_id_object_id_idx")
+                    .table(Keys::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("keys_tenant_id_updated_at_rule_id_object_id_idx")
+                    .table(Keys::Table)
+                    .col(TenantId)
+                    .col(Keys::UpdatedAt)
+                    .col(Keys::RuleId)
+                    .col(Keys::ObjectId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("keys_tenant_id_updated_at_rule_id_object_id_idx")
+                    .table(Keys::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("keys_updated_at_rule_id_object_id_idx")
+                    .table(Keys::Table)
+                    .col(Keys::UpdatedAt)
+                    .col(Keys::RuleId)
+                    .col(Keys::ObjectId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("objects_tenant_id_updated_at_id_idx")
+                    .table(Objects::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("objects_updated_at_id_idx")
+                    .table(Objects::Table)
+                    .col(Objects::UpdatedAt)
+                    .col(Id)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("role_bindings_tenant_id_subject_role_idx")
+                    .table(RoleBindings::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("role_bindings_subject_role_idx")
+                    .table(RoleBindings::Table)
+                    .col(RoleBindings::Subject)
+                    .col(RoleBindings::Role)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("objects_tenant_id_source_external_id_key")
+                    .table(Objects::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("objects_source_external_id_key")
+                    .table(Objects::Table)
+                    .col(Objects::Source)
+                    .col(Objects::ExternalId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        replace_foreign_key(
+            manager,
+            Keys::Table,
+            "keys_fk_objects",
+            ForeignKey::create()
+                .name("keys_fk_objects")
+                .from(Keys::Table, Keys::ObjectId)
+                .to(Objects::Table, Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+        replace_foreign_key(
+            manager,
+            Keys::Table,
+            "keys_fk_rules",
+            ForeignKey::create()
+                .name("keys_fk_rules")
+                .from(Keys::Table, Keys::RuleId)
+                .to(Rules::Table, Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+        replace_foreign_key(
+            manager,
+            Rules::Table,
+            "rules_fk_fields",
+            ForeignKey::create()
+                .name("rules_fk_fields")
+                .from(Rules::Table, Rules::FieldId)
+                .to(Fields::Table, Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+
+        for (name, table) in [
+            ("fields_tenant_id_id_key", Fields::Table.into_iden()),
+            ("rules_tenant_id_id_key", Rules::Table.into_iden()),
+            ("objects_tenant_id_id_key", Objects::Table.into_iden()),
+        ] {
+            manager
+                .drop_index(Index::drop().name(name).table(table).to_owned())
+                .await?;
+        }
+
+        for table in tables() {
+            manager
+                .alter_table(Table::alter().table(table).drop_column(TenantId).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn tables() -> Vec<DynIden> {
+    vec![
+        Fields::Table.into_iden(),
+        Rules::Table.into_iden(),
+        Objects::Table.into_iden(),
+        Keys::Table.into_iden(),
+        Jobs::Table.into_iden(),
+        Outbox::Table.into_iden(),
+        DeadLetters::Table.into_iden(),
+        ProcessedMessages::Table.into_iden(),
+        KeySyncCheckpoints::Table.into_iden(),
+        ApiKeys::Table.into_iden(),
+        RoleBindings::Table.into_iden(),
+    ]
+}
+
+async fn replace_foreign_key<T: IntoTableRef>(
+    manager: &SchemaManager<'_>,
+    table: T,
+    name: &str,
+    foreign_key: ForeignKeyCreateStatement,
+) -> Result<(), DbErr> {
+    manager
+        .drop_foreign_key(ForeignKey::drop().name(name).table(table).to_owned())
+        .await?;
+    manager.create_foreign_key(foreign_key).await
+}
+
+#[derive(Iden)]
+struct TenantId;
+
+#[derive(Iden)]
+struct Id;
+
+#[derive(Iden)]
+enum Fields {
+    Table,
+}
+
+#[derive(Iden)]
+enum Rules {
+    Table,
+    FieldId,
+}
+
+#[derive(Iden)]
+enum Objects {
+    Table,
+    UpdatedAt,
+    Source,
+    ExternalId,
+}
+
+#[derive(Iden)]
+enum Keys {
+    Table,
+    RuleId,
+    ObjectId,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Jobs {
+    Table,
+}
+
+#[derive(Iden)]
+enum Outbox {
+    Table,
+}
+
+#[derive(Iden)]
+enum DeadLetters {
+    Table,
+}
+
+#[derive(Iden)]
+enum ProcessedMessages {
+    Table,
+}
+
+#[derive(Iden)]
+enum KeySyncCheckpoints {
+    Table,
+}
+
+#[derive(Iden)]
+enum ApiKeys {
+    Table,
+}
+
+#[derive(Iden)]
+enum RoleBindings {
+    Table,
+    Subject,
+    Role,
+}
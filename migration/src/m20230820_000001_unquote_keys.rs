@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // keys of equals rules were stored JSON-quoted, e.g. `"222"`, decoding them also
+        // removes escapes. Keys of regex rules are left as they are, since their pattern may
+        // match the quotes, and are written unquoted once the rule is updated.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "keys" SET "key" = "key"::jsonb #>> '{}'
+                WHERE "key" LIKE '"%"'
+                AND "rule_id" IN (SELECT "id" FROM "rules" WHERE "type" = 'Equals')"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "keys" SET "key" = to_jsonb("key")::text
+                WHERE "rule_id" IN (SELECT "id" FROM "rules" WHERE "type" = 'Equals')"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}